@@ -0,0 +1,76 @@
+// --- BENCHMARKS DE PRESUPUESTO (feature "bench") ---
+
+// Mide el coste en CPU y memoria de las operaciones principales con distintos volúmenes
+// de datos y comprueba que siguen por debajo de los límites de Soroban por transacción.
+// Ejecutar con: make bench
+
+extern crate std;
+
+use soroban_sdk::{testutils::Address as _, Address, Env, String};
+
+use crate::{testutils::seed_tasks, ToDoListContract, ToDoListContractClient};
+
+// Límites de red por transacción
+const MAX_CPU_INSNS: u64 = 100_000_000;
+const MAX_MEM_BYTES: u64 = 41_943_040;
+
+// Volúmenes de tareas existentes con los que se mide cada operación
+const DATA_SIZES: [u32; 3] = [10, 100, 250];
+
+fn setup(n: u32) -> (Env, ToDoListContractClient<'static>, Address) {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register(ToDoListContract, (Address::generate(&env),));
+    let client = ToDoListContractClient::new(&env, &contract_id);
+    let owner = Address::generate(&env);
+    seed_tasks(&env, &contract_id, &owner, n);
+
+    (env, client, owner)
+}
+
+// Ejecuta 'op' con el presupuesto reiniciado y retorna (cpu, memoria) consumidos
+fn measure<F: FnOnce()>(env: &Env, name: &str, size: u32, op: F) -> (u64, u64) {
+    let mut budget = env.cost_estimate().budget();
+    budget.reset_default();
+    op();
+
+    let cpu = budget.cpu_instruction_cost();
+    let mem = budget.memory_bytes_cost();
+    std::println!("{name} (n={size}): cpu={cpu} mem={mem}");
+
+    assert!(cpu < MAX_CPU_INSNS, "{name} (n={size}) supera el límite de CPU");
+    assert!(mem < MAX_MEM_BYTES, "{name} (n={size}) supera el límite de memoria");
+    (cpu, mem)
+}
+
+#[test]
+fn bench_add_task() {
+    for size in DATA_SIZES {
+        let (env, client, owner) = setup(size);
+        let desc = String::from_str(&env, "bench");
+        measure(&env, "add_task", size, || {
+            client.add_task(&desc, &owner);
+        });
+    }
+}
+
+#[test]
+fn bench_task_completed() {
+    for size in DATA_SIZES {
+        let (env, client, owner) = setup(size);
+        measure(&env, "task_completed", size, || {
+            client.task_completed(&u64::from(size), &owner);
+        });
+    }
+}
+
+#[test]
+fn bench_get_all() {
+    for size in DATA_SIZES {
+        let (env, client, _) = setup(size);
+        measure(&env, "get_all", size, || {
+            assert_eq!(client.get_all().len(), size);
+        });
+    }
+}
@@ -0,0 +1,53 @@
+// --- INTERFAZ INTEROPERABLE DE TABLERO DE TAREAS ---
+
+// Firmas mínimas (crear, leer, concluir, transferir) que otros contratos del workspace pueden
+// usar para hablar con cualquier tablero compatible. '#[contractclient]' genera 'TaskBoardClient'
+// para invocarlo por su dirección.
+
+use soroban_sdk::{contractclient, Address, Bytes, Env, String};
+
+use crate::{StatsCounters, Task, TaskError, ToDoListContract};
+
+#[contractclient(name = "TaskBoardClient")]
+pub trait TaskBoard {
+    fn add_task(env: Env, description: String, owner: Address) -> Result<u64, TaskError>;
+
+    fn get_task_by_id(env: Env, task_id: u64) -> Option<Task>;
+
+    fn task_completed(env: Env, task_id: u64, caller: Address) -> Result<(), TaskError>;
+
+    fn transfer_ownership(env: Env, task_id: u64, caller: Address, new_owner: Address) -> Result<(), TaskError>;
+}
+
+// Este contrato cumple la interfaz delegando en sus funciones públicas
+impl TaskBoard for ToDoListContract {
+    fn add_task(env: Env, description: String, owner: Address) -> Result<u64, TaskError> {
+        ToDoListContract::add_task(env, description, owner)
+    }
+
+    fn get_task_by_id(env: Env, task_id: u64) -> Option<Task> {
+        ToDoListContract::get_task_by_id(env, task_id)
+    }
+
+    fn task_completed(env: Env, task_id: u64, caller: Address) -> Result<(), TaskError> {
+        ToDoListContract::task_completed(env, task_id, caller)
+    }
+
+    fn transfer_ownership(env: Env, task_id: u64, caller: Address, new_owner: Address) -> Result<(), TaskError> {
+        ToDoListContract::transfer_ownership(env, task_id, caller, new_owner)
+    }
+}
+
+// Oráculo que confirma una afirmación externa ("feed actualizado", "PR fusionado") antes de
+// dar por concluida una tarea. '#[contractclient]' genera 'TaskOracleClient'.
+#[contractclient(name = "TaskOracleClient")]
+pub trait TaskOracle {
+    fn verify(env: Env, task_id: u64, claim: Bytes) -> bool;
+}
+
+// Contrato compañero que almacena las estadísticas agregadas que 'flush_stats' le envía.
+// '#[contractclient]' genera 'StatsSinkClient'.
+#[contractclient(name = "StatsSinkClient")]
+pub trait StatsSink {
+    fn record_stats(env: Env, source: Address, stats: StatsCounters);
+}
@@ -1,249 +1,4788 @@
 #![no_std]
 use soroban_sdk::{
-    contract, contractimpl, contracterror, contracttype, Env, String, Symbol, Vec, Address, symbol_short
+    contract, contractimpl, contracterror, contracttype, xdr::ToXdr, Bytes, BytesN, Env, IntoVal, String, Symbol, TryFromVal, Val, Vec, Address, symbol_short
 };
 
+use crate::interface::{StatsSinkClient, TaskOracleClient};
+
 // --- TIPOS DE DATOS Y ERRORES ---
 
-// Enum con los posibles estados de las tareas
-#[contracttype]
-#[derive(Clone, Debug, Eq, PartialEq)]
-pub enum TaskStatus {
-    Completed,
-    Pending,
-    Deleted,
-}
+// Enum con los posibles estados de las tareas
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum TaskStatus {
+    Completed,
+    Pending,
+    Deleted,
+    Scheduled, // Se activa (pasa a Pending) al llegar su 'scheduled_for'
+    Blocked,   // Trabajo detenido por un motivo explícito (ver TaskDetail)
+    Cancelled, // Terminal: no se hará, pero sigue visible en el historial (a diferencia de Deleted)
+    WaitingOn, // A la espera de otra persona (ver 'get_waiting')
+    Backlog,   // Algún día / quizá: fuera de los listados y contadores habituales (ver 'get_backlog')
+}
+
+// Enum con los niveles de prioridad de las tareas
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum TaskPriority {
+    Low,
+    Medium,
+    High,
+    Critical,
+}
+
+// Estructura de una tarea (esquema v3), con 'owner' como Address e ID u64
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Task {
+    pub id: u64,
+    pub description: String,
+    pub owner: Address,
+    pub status: TaskStatus,
+    pub timestamp: u64,          // Creación
+    pub priority: TaskPriority,
+    pub due_date: Option<u64>,   // Fecha límite (epoch UNIX), opcional
+    pub updated_at: u64,         // Última modificación
+}
+
+// Esquema v1 de una tarea, tal como se guardaba antes de la migración (clave: ID u32)
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TaskV1 {
+    pub id: u32,
+    pub description: String,
+    pub owner: Address,
+    pub status: TaskStatus,
+    pub timestamp: u64,
+}
+
+// Esquema v2 de una tarea: el actual con ID u32 (clave: DataKey::Task con ID u32)
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TaskV2 {
+    pub id: u32,
+    pub description: String,
+    pub owner: Address,
+    pub status: TaskStatus,
+    pub timestamp: u64,
+    pub priority: TaskPriority,
+    pub due_date: Option<u64>,
+    pub updated_at: u64,
+}
+
+// Conversión v1 -> v3 rellenando los campos nuevos con valores por defecto
+impl From<TaskV1> for Task {
+    fn from(old: TaskV1) -> Self {
+        Task {
+            id: u64::from(old.id),
+            description: old.description,
+            owner: old.owner,
+            status: old.status,
+            timestamp: old.timestamp,
+            priority: TaskPriority::Medium,
+            due_date: None,
+            updated_at: old.timestamp,
+        }
+    }
+}
+
+// Conversión v2 -> v3: solo cambia el tipo del ID
+impl From<TaskV2> for Task {
+    fn from(old: TaskV2) -> Self {
+        Task {
+            id: u64::from(old.id),
+            description: old.description,
+            owner: old.owner,
+            status: old.status,
+            timestamp: old.timestamp,
+            priority: old.priority,
+            due_date: old.due_date,
+            updated_at: old.updated_at,
+        }
+    }
+}
+
+// Enum de errores personalizados
+#[contracterror]
+#[repr(u32)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+pub enum TaskError {
+    TaskNotFound = 1,
+    InvalidTaskData = 2,
+    Unauthorized = 3,
+    TaskAlreadyCompleted = 4, // Usado también si se intenta modificar una tarea no-Pendiente
+    InvalidSessionKey = 5,    // Session key igual al propietario o con expiración ya vencida
+    TaskFrozen = 6,           // La tarea está congelada y no admite modificaciones
+    AccountFrozen = 7,        // La cuenta fue congelada por el admin
+    MigrationNotNeeded = 8,   // El almacenamiento ya está en la versión actual
+    TaskCancelled = 9,        // La tarea fue cancelada (estado terminal)
+    PomodoroInProgress = 10,  // Ya hay un pomodoro activo, o aún no ha terminado su duración
+    NoActivePomodoro = 11,    // No hay ningún pomodoro activo en la tarea
+    NotAHabit = 12,           // La tarea no se creó como hábito
+    AlreadyCheckedIn = 13,    // El hábito ya se marcó hoy
+    GoalNotFound = 14,
+    PurgeTooSoon = 15,        // Aún no ha pasado el periodo de gracia desde el borrado
+    NothingToUndo = 16,       // No hay versión anterior guardada, o ya pasó la ventana para deshacer
+    TaskLocked = 17,          // Otra dirección tiene la tarea bloqueada para edición exclusiva
+    InvalidReferral = 18,     // Auto-referido, referido circular o el usuario ya no es nuevo
+    ActionNotFound = 19,      // No hay ninguna acción programada con ese ID
+    TimelockNotElapsed = 20,  // La acción programada aún no puede ejecutarse
+    HashMismatch = 21,        // El texto revelado (o la prueba) no corresponde al hash comprometido
+    ProofRequired = 22,       // La tarea solo se puede concluir con 'complete_with_proof'
+    OracleRejected = 23,      // El oráculo de la tarea no confirmó su afirmación
+    CounterOverflow = 24,     // Un contador de IDs llegó a su máximo
+    PaginationRequired = 25,  // Demasiadas tareas para listarlas de una vez: usar 'get_all_page'
+    TaskIsDeleted = 26,       // La tarea existe pero está en la papelera: usar 'get_deleted_task'
+    InvalidTransition = 27,   // El cambio de estado no está permitido desde el estado actual
+    TaskNotTransferable = 28, // Las tareas completadas o eliminadas no se transfieren (ver 'SetTransferClosed')
+    NoChange = 29,            // La modificación dejaría la tarea igual que está
+    SelfTransfer = 30,        // El nuevo propietario es el mismo que el actual
+    DependencyCycle = 31,     // La dependencia cerraría un ciclo (o el grafo es demasiado grande)
+}
+
+// Session key temporal registrada por un propietario (válida hasta 'expires_at', inclusive)
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SessionKey {
+    pub key: Address,
+    pub expires_at: u32, // Número de secuencia del ledger
+}
+
+// Vista detallada de una tarea con la información guardada fuera del registro principal
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TaskDetail {
+    pub task: Task,
+    pub blocked_reason: Option<String>, // Solo si la tarea está Blocked
+    pub description_encrypted: Option<Bytes>, // Texto cifrado si la descripción no es pública
+    pub color: Option<u32>,     // Color de presentación (0xRRGGBB)
+    pub icon: Option<Symbol>,   // Icono de presentación
+}
+
+// Pomodoro en curso sobre una tarea
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Pomodoro {
+    pub started_at: u64,
+    pub duration: u64, // Segundos
+}
+
+// Estadísticas de concentración (por tarea o por propietario)
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FocusStats {
+    pub sessions: u32,
+    pub focused_secs: u64,
+}
+
+// Estado de una tarea en modo hábito (un check-in por día)
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct HabitState {
+    pub current_streak: u32,
+    pub best_streak: u32,
+    pub total_check_ins: u32,
+    pub last_check_in_day: Option<u64>, // Día (desde epoch) en la zona horaria del propietario
+}
+
+// Objetivo que agrega tareas (estilo OKR): se avanza al completar sus tareas vinculadas
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Goal {
+    pub id: u32,
+    pub owner: Address,
+    pub name: String,
+    pub target_count: u32,
+    pub deadline: u64,
+    pub progress: u32, // Tareas vinculadas completadas
+}
+
+// Objetivo con su estado calculado respecto a la meta y la fecha límite
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct GoalStatus {
+    pub goal: Goal,
+    pub achieved: bool,
+    pub past_deadline: bool,
+}
+
+// Versión anterior de una tarea, guardada en cada modificación para poder deshacerla
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct UndoSnapshot {
+    pub task: Task,
+    pub saved_at: u64, // Momento de la modificación que la reemplazó
+}
+
+// Bloqueo de edición exclusiva sobre una tarea (almacenamiento temporal)
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TaskLock {
+    pub holder: Address,
+    pub expires_at: u32, // Número de secuencia del ledger (inclusive)
+}
+
+// Referido de un usuario nuevo: el referidor gana puntos cuando completa sus primeras tareas
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Referral {
+    pub referrer: Address,
+    pub completed: u32, // Tareas completadas por el referido hasta recibir la recompensa
+    pub rewarded: bool,
+}
+
+// Contexto GTD: dónde o con qué se puede hacer una tarea
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum TaskContext {
+    Home,     // @casa
+    Office,   // @oficina
+    Errands,  // @recados
+    Phone,    // @teléfono
+    Computer, // @ordenador
+}
+
+// Energía que requiere una tarea (para sugerir qué hacer según el ánimo disponible)
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum EnergyLevel {
+    Low,
+    Medium,
+    High,
+}
+
+// Esfuerzo estimado de una tarea: energía necesaria y duración en minutos
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TaskEffort {
+    pub energy: EnergyLevel,
+    pub minutes: u32,
+}
+
+// Tarea a la espera de un tercero: a quién se espera y desde cuándo
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct WaitingItem {
+    pub task: Task,
+    pub who: String,
+    pub since: u64,
+}
+
+// Revisión periódica de una tarea: cada 'every_secs' segundos desde la última
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ReviewSchedule {
+    pub every_secs: u64,
+    pub last_reviewed_at: u64,
+}
+
+// Fila del diagrama de Gantt de un proyecto ('get_timeline')
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TimelineEntry {
+    pub task_id: u64,
+    pub start_estimate: u64,   // Inicio estimado (epoch UNIX); 0 si la tarea ya está cerrada
+    pub duration_minutes: u32, // Duración estimada (0 si no se ha estimado)
+    pub due_date: u64,         // 0 = sin fecha límite
+    pub dependencies: Vec<u64>,
+    pub status: TaskStatus,
+}
+
+// Qué pasa con la tarea original al dividirla en subtareas
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SplitMode {
+    Replace,   // Va a la papelera: las subtareas la sustituyen
+    Container, // Sigue pendiente como contenedor de sus subtareas
+}
+
+// Tipo de relación entre dos tareas enlazadas ('link_tasks')
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum LinkType {
+    RelatesTo,   // Relacionadas, sin más
+    DuplicateOf, // Una repite a la otra
+    Blocks,      // Una impide avanzar a la otra (solo informativo, no es una dependencia)
+}
+
+// Enlace guardado en cada extremo: 'outgoing' es true en la tarea origen ('a' en 'link_tasks')
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TaskLink {
+    pub task_id: u64,
+    pub relation: LinkType,
+    pub outgoing: bool,
+}
+
+// Roles para repartir las tareas de administración entre varias direcciones
+// (el admin los asigna y conserva todos los permisos)
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Role {
+    Upgrader,  // Programa actualizaciones del contrato (con timelock)
+    Pauser,    // Congela y descongela tareas
+    Treasurer, // Reservado para la gestión de fondos
+    Moderator, // Oculta del listado público las tareas que incumplen las normas
+}
+
+// Cambios de configuración que el admin solo puede aplicar a través del timelock
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum AdminAction {
+    SetPurgeGrace(u64), // Segundos entre el borrado y la purga
+    SetAdmin(Address),  // Traspasar la administración (p.ej. a un contrato DAO o multisig)
+    SetActionDelay(u64), // Nueva espera obligatoria del timelock (segundos)
+    Upgrade(BytesN<32>), // Actualizar el WASM del contrato (hash ya subido a la red)
+    ReassignTask(u64, Address, String), // Recuperación: (ID de tarea, nuevo propietario, motivo)
+    SetStatsSink(Option<Address>), // Contrato que recibe 'flush_stats' (None lo desactiva)
+    SetPublicEnumeration(bool), // false: solo el admin puede listar todas las tareas
+    SetTtlPolicy(u32, u32), // (umbral, extensión) en ledgers para la renovación automática del TTL
+    SetTransferClosed(bool), // true: 'transfer_ownership' acepta también tareas completadas o eliminadas
+}
+
+// Registro de una reasignación de emergencia hecha por el admin
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RecoveryRecord {
+    pub previous_owner: Address,
+    pub new_owner: Address,
+    pub reason: String,
+    pub executed_at: u64,
+}
+
+// Acción programada, ejecutable a partir de 'eta'
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PendingAction {
+    pub action: AdminAction,
+    pub eta: u64,
+}
+
+// Actividad de un propietario en un día (UTC)
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DayStats {
+    pub created: u32,
+    pub completed: u32,
+    pub open_end: u32, // Tareas abiertas del propietario tras la última operación del día
+}
+
+// Resumen de una semana para la revisión semanal
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct WeeklyReport {
+    pub created: u32,
+    pub completed: u32,
+    pub overdue: u32,      // Con fecha límite en la semana, ya vencida y aún abiertas
+    pub carried_over: u32, // Abiertas al empezar la semana (arrastradas de semanas anteriores)
+}
+
+// Formato de exportación estable: solo tipos primitivos, sin enums ni Option, para que las
+// herramientas externas no dependan de la evolución de 'Task'. Los códigos no cambian entre versiones.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TaskExport {
+    pub id: u64,
+    pub description: String,
+    pub owner: Address,
+    pub status: u32,   // 0 Pending, 1 Completed, 2 Deleted, 3 Scheduled, 4 Blocked, 5 Cancelled, 6 WaitingOn, 7 Backlog
+    pub timestamp: u64,
+    pub priority: u32, // 0 Low, 1 Medium, 2 High, 3 Critical
+    pub due_date: u64, // 0 = sin fecha límite
+    pub updated_at: u64,
+}
+
+// Verificación externa exigida para concluir una tarea
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct OracleCheck {
+    pub oracle: Address, // Contrato oráculo registrado por el admin
+    pub claim: Bytes,    // Afirmación que el oráculo debe confirmar
+}
+
+// Contadores agregados desde el último envío al contrato de estadísticas
+#[contracttype]
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct StatsCounters {
+    pub created: u32,
+    pub completed: u32,
+    pub deleted: u32,
+    pub cancelled: u32,
+}
+
+// Lo que queda de una tarea archivada en frío: el hash de su registro completo (emitido en el
+// evento 'archived') basta para verificarlo al restaurarla
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ColdTombstone {
+    pub hash: BytesN<32>,
+    pub status: TaskStatus,
+    pub archived_at: u64,
+}
+
+// Entradas de almacenamiento atribuibles a un propietario, para estimar su coste de renta
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FootprintReport {
+    pub task_entries: u32,      // Registros de tareas vivos en su índice
+    pub side_entries: u32,      // Entradas auxiliares por tarea (hábitos, deshacer, archivadas...)
+    pub owner_entries: u32,     // Entradas propias del propietario (índice, zona horaria, días con estadísticas...)
+    pub description_bytes: u32, // Bytes de descripciones, en claro y cifradas
+    pub index_buckets: u32,     // Cubos del índice global por estado que contienen sus tareas
+}
+
+// Página de un listado: 'next_cursor' es el ID desde el que pedir la siguiente (None = fin)
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TaskPage {
+    pub tasks: Vec<Task>,
+    pub next_cursor: Option<u64>,
+}
+
+// Tramo del cálculo de la raíz Merkle: 'frontier' es el acumulador que se pasa a la siguiente
+// llamada junto con 'next_cursor'; 'root' solo llega en el último tramo (next_cursor = None)
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct StateRootPage {
+    pub frontier: Vec<BytesN<32>>,
+    pub next_cursor: Option<u64>,
+    pub root: Option<BytesN<32>>,
+}
+
+// Discrepancias entre el índice de un propietario y los registros reales de tareas
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct IntegrityReport {
+    pub missing_ids: Vec<u64>,    // Tareas del propietario que no aparecen en su índice
+    pub stale_ids: Vec<u64>,      // Entradas del índice sin registro de tarea (o duplicadas)
+    pub mismatched_ids: Vec<u64>, // Entradas del índice cuya tarea tiene otro 'owner'
+}
+
+// Claves de almacenamiento compuestas
+#[contracttype]
+#[derive(Clone)]
+pub enum DataKey {
+    Task(u64),           // ID -> Task (esquema actual)
+    SessionKey(Address), // Propietario -> SessionKey
+    FrozenTask(u64),     // ID de tarea -> Address que la congeló
+    FrozenAccount(Address), // Cuentas congeladas por el admin
+    ScheduledFor(u64),   // ID de tarea programada -> momento de activación (epoch UNIX)
+    PriorityAging(Address), // Propietario -> antigüedad (segundos) que sube un nivel de prioridad
+    AgedAt(u64),         // ID de tarea -> referencia desde la que se mide su envejecimiento
+    BlockedReason(u64),  // ID de tarea bloqueada -> motivo
+    EditGrant(u64, Address), // (ID de tarea, editor) -> expiración del permiso (epoch UNIX)
+    ActivePomodoro(u64), // ID de tarea -> Pomodoro en curso
+    TaskFocus(u64),      // ID de tarea -> FocusStats
+    OwnerFocus(Address), // Propietario -> FocusStats
+    Habit(u64),          // ID de tarea hábito -> HabitState
+    UtcOffset(Address),  // Propietario -> desfase horario en segundos
+    Goal(u32),           // ID de objetivo -> Goal
+    OwnerGoals(Address), // Propietario -> Vec<u32> de IDs de objetivos
+    TaskGoal(u64),       // ID de tarea -> ID del objetivo al que está vinculada
+    DayStats(Address, u64), // (Propietario, día UTC) -> DayStats
+    ActiveDays(Address), // Propietario -> Vec<u64> ordenado de días con DayStats
+    OpenCount(Address),  // Propietario -> tareas abiertas (Pending, Scheduled o Blocked)
+    DueOpen(Address, u64), // (Propietario, día UTC) -> tareas abiertas que vencen ese día
+    DeletedAt(u64),      // ID de tarea eliminada -> momento del borrado (epoch UNIX)
+    UndoSnapshot(u64),   // ID de tarea -> UndoSnapshot (una sola versión anterior)
+    TaskLock(u64),       // ID de tarea -> TaskLock (almacenamiento temporal)
+    Nonce(Address),      // Delegado -> próximo nonce para sus operaciones delegadas
+    Referral(Address),   // Usuario referido -> Referral
+    ReferralPoints(Address), // Referidor -> puntos acumulados
+    PendingAction(u32),  // ID de acción -> PendingAction
+    Recovery(u64),       // ID de tarea -> RecoveryRecord de su última reasignación por el admin
+    Role(Role, Address), // (Rol, dirección) -> true si lo tiene asignado
+    Hidden(u64),         // ID de tarea ocultada por moderación -> motivo
+    DescriptionHash(u64), // ID de tarea privada -> sha256 de la descripción aún no revelada
+    EncryptedDescription(u64), // ID de tarea -> descripción cifrada (Bytes)
+    EncryptionPubkey(Address), // Propietario -> clave pública para cifrar sus descripciones
+    CompletionCommitment(u64), // ID de tarea -> sha256 de la preimagen que la concluye
+    CompletionProof(u64), // ID de tarea -> preimagen revelada al concluirla
+    Oracle(Address),     // Oráculos registrados por el admin
+    TaskOracle(u64),     // ID de tarea -> OracleCheck
+    OwnerSeq(Address, u32), // (Propietario creador, nº de secuencia) -> ID global
+    OwnerNextSeq(Address), // Propietario -> próximo nº de secuencia
+    TaskSeq(u64),        // ID global -> (propietario creador, nº de secuencia)
+    StatusBucket(TaskStatus, u32), // (Estado, ID / STATUS_BUCKET_SIZE) -> Vec<u64> ordenado de IDs
+    StatusBuckets(TaskStatus), // Estado -> Vec<u32> ordenado de los cubos no vacíos
+    Cold(u64),           // ID de tarea archivada -> ColdTombstone
+}
+
+// Claves de la organización de las tareas (idioma, aspecto, lugar, contexto, esfuerzo, esperas,
+// revisiones, dependencias, subtareas, fusiones y enlaces), aparte de DataKey para no pasar del
+// límite de variantes de un enum #[contracttype]
+#[contracttype]
+#[derive(Clone)]
+pub enum OrgKey {
+    TaskLang(u64),       // ID de tarea -> idioma de la descripción (p. ej. "es", "en")
+    TaskColor(u64),      // ID de tarea -> color de presentación (0xRRGGBB)
+    TaskIcon(u64),       // ID de tarea -> icono de presentación
+    TaskLocation(u64),   // ID de tarea -> (latitud, longitud) en millonésimas de grado
+    GeoCell(i32, i32),   // Celda de la rejilla (lat / GEO_CELL, lon / GEO_CELL) -> Vec<u64> de IDs
+    TaskContext(u64),    // ID de tarea -> TaskContext
+    ContextTasks(Address, TaskContext), // (Propietario, contexto) -> Vec<u64> de IDs
+    TaskEffort(u64),     // ID de tarea -> TaskEffort
+    EnergyTasks(Address, EnergyLevel), // (Propietario, energía) -> Vec<u64> de IDs
+    WaitingOn(u64),      // ID de tarea en espera -> (a quién se espera, desde cuándo)
+    Review(u64),         // ID de tarea -> ReviewSchedule
+    ReviewTasks(Address), // Propietario -> Vec<u64> de IDs con revisión periódica
+    Dependencies(u64),   // ID de tarea -> Vec<u64> de tareas de las que depende
+    Dependents(u64),     // ID de tarea -> Vec<u64> de tareas que dependen de ella
+    DependencyRequests(u64), // ID de tarea ajena -> Vec<u64> de tareas que piden depender de ella
+    Subtasks(u64),       // ID de tarea dividida -> Vec<u64> de subtareas
+    ParentTask(u64),     // ID de subtarea -> ID de la tarea de la que salió
+    MergedInto(u64),     // ID de tarea duplicada -> ID de la tarea con la que se fusionó
+    MergedFrom(u64),     // ID de tarea principal -> Vec<u64> de duplicadas fusionadas en ella
+    TaskLinks(u64),      // ID de tarea -> Vec<TaskLink> (cada enlace se guarda en ambos extremos)
+}
+
+// Claves del esquema v2 con el ID de tarea como u32 (mismos nombres que en DataKey y OrgKey,
+// así que se serializan igual que entonces). Solo se usan para migrar al esquema v3.
+#[contracttype]
+#[derive(Clone)]
+enum LegacyKey {
+    Task(u32),
+    FrozenTask(u32),
+    ScheduledFor(u32),
+    AgedAt(u32),
+    BlockedReason(u32),
+    ActivePomodoro(u32),
+    TaskFocus(u32),
+    Habit(u32),
+    TaskGoal(u32),
+    DeletedAt(u32),
+    UndoSnapshot(u32),
+    Recovery(u32),
+    Hidden(u32),
+    DescriptionHash(u32),
+    EncryptedDescription(u32),
+    CompletionCommitment(u32),
+    CompletionProof(u32),
+    TaskOracle(u32),
+    TaskSeq(u32),
+    Cold(u32),
+    TaskLang(u32),
+    TaskColor(u32),
+    TaskIcon(u32),
+    TaskLocation(u32),
+    TaskContext(u32),
+    TaskEffort(u32),
+    WaitingOn(u32),
+    Review(u32),
+    Dependencies(u32),
+    Dependents(u32),
+    DependencyRequests(u32),
+    Subtasks(u32),
+    ParentTask(u32),
+    MergedInto(u32),
+    MergedFrom(u32),
+    TaskLinks(u32),
+}
+
+// Enlace entre tareas del esquema v2 (ID u32)
+#[contracttype]
+#[derive(Clone)]
+struct TaskLinkV2 {
+    task_id: u32,
+    relation: LinkType,
+    outgoing: bool,
+}
+
+// --- CONTRATO Y CONSTANTES ---
+
+#[contract]
+pub struct ToDoListContract;
+
+// Constante para la clave del próximo ID
+const NEXT_ID_KEY: Symbol = symbol_short!("next_id");
+
+// Constante para la clave del administrador del contrato
+const ADMIN_KEY: Symbol = symbol_short!("admin");
+
+// Versión del esquema de almacenamiento y cursor de la migración por lotes
+const STORAGE_VERSION_KEY: Symbol = symbol_short!("st_ver");
+const MIGRATION_CURSOR_KEY: Symbol = symbol_short!("mig_cur");
+const STORAGE_VERSION: u32 = 3;
+
+// Constante para la clave de la lista de tareas programadas pendientes de activar
+const SCHEDULED_KEY: Symbol = symbol_short!("sched");
+
+// Duración de un día y desfase horario máximo admitido (UTC-14h .. UTC+14h)
+const DAY_SECS: u64 = 86_400;
+const MAX_UTC_OFFSET: i32 = 14 * 3600;
+
+// Constante para la clave del número de secuencia de los eventos (la clave conserva su nombre
+// anterior, "op_seq", para no perder la cuenta de los despliegues existentes)
+const EVENT_SEQ_KEY: Symbol = symbol_short!("op_seq");
+
+// Periodo de gracia (segundos) entre el borrado y la purga definitiva; configurable por el admin
+const PURGE_GRACE_KEY: Symbol = symbol_short!("purge_gr");
+const DEFAULT_PURGE_GRACE: u64 = 7 * DAY_SECS;
+
+// Ventana (segundos) durante la que se puede deshacer la última modificación de una tarea
+const UNDO_WINDOW: u64 = 300;
+
+// Duración máxima (segundos) de una sesión pomodoro
+const MAX_POMODORO_SECS: u64 = 4 * 3600;
+
+// Tareas que debe completar un referido para que su referidor gane REFERRAL_POINTS
+const REFERRAL_THRESHOLD: u32 = 3;
+const REFERRAL_POINTS: u32 = 10;
+
+// Timelock de las acciones de administración: próximo ID y espera obligatoria (segundos),
+// configurable a su vez mediante el timelock
+const NEXT_ACTION_ID_KEY: Symbol = symbol_short!("act_nid");
+const ACTION_DELAY_KEY: Symbol = symbol_short!("act_dly");
+const DEFAULT_ACTION_DELAY: u64 = 2 * DAY_SECS;
+
+// Longitud máxima (bytes) de una descripción (también de la que se revela contra su hash)
+// y mínima (caracteres, sin contar espacios de los extremos)
+const MAX_DESCRIPTION_LEN: usize = 256;
+const MIN_DESCRIPTION_LEN: usize = 2;
+
+// Contrato de estadísticas configurado y contadores pendientes de enviarle
+const STATS_SINK_KEY: Symbol = symbol_short!("st_sink");
+const STATS_KEY: Symbol = symbol_short!("stats");
+
+// Listado global: si es público (por defecto) y límites por llamada
+const PUBLIC_ENUM_KEY: Symbol = symbol_short!("pub_enum");
+const GET_ALL_MAX_IDS: u64 = 500;
+const MAX_PAGE_SIZE: u32 = 100;
+
+// Renovación automática del TTL del almacenamiento en cada llamada que lee o escribe tareas (una
+// vez por llamada): si quedan menos de 'umbral' ledgers se amplía hasta 'extensión' (configurable
+// con el timelock)
+const TTL_POLICY_KEY: Symbol = symbol_short!("ttl_pol");
+const LEDGERS_PER_DAY: u32 = 17_280;
+const DEFAULT_TTL_THRESHOLD: u32 = 30 * LEDGERS_PER_DAY;
+const DEFAULT_TTL_EXTEND_TO: u32 = 120 * LEDGERS_PER_DAY;
+
+// Si 'transfer_ownership' admite tareas completadas o eliminadas (por defecto no)
+const TRANSFER_CLOSED_KEY: Symbol = symbol_short!("xfer_cls");
+
+// Ubicación de las tareas en millonésimas de grado: tamaño de celda de la rejilla (0,01° ≈ 1,1 km)
+// y radio máximo de 'get_tasks_near' (se consultan como mucho 5x5 celdas)
+const GEO_CELL: i32 = 10_000;
+const MAX_NEAR_RADIUS: i32 = 2 * GEO_CELL;
+
+// IDs por cubo del índice global por estado
+const STATUS_BUCKET_SIZE: u64 = 64;
+
+// Máximo de IDs por llamada a 'export_range'
+const MAX_EXPORT_RANGE: u64 = 100;
+
+// Longitud máxima (bytes) de una descripción cifrada
+const MAX_CIPHERTEXT_LEN: u32 = 1024;
+
+// Dependencias: máximo por tarea y tareas que se recorren al buscar ciclos
+const MAX_DEPENDENCIES: u32 = 20;
+const MAX_DEPENDENCY_SCAN: u32 = 200;
+
+// Máximo de partes en que se puede dividir una tarea
+const MAX_SPLIT_PARTS: u32 = 10;
+
+// Máximo de enlaces por tarea
+const MAX_TASK_LINKS: u32 = 20;
+
+// Máximo de tareas de un objetivo (proyecto) para los cálculos de planificación
+const MAX_PROJECT_TASKS: u32 = 50;
+
+// Constante para la clave del próximo ID de objetivo
+const NEXT_GOAL_ID_KEY: Symbol = symbol_short!("goal_nid");
+
+
+// --- IMPLEMENTACIÓN DEL CONTRATO ---
+
+#[contractimpl]
+impl ToDoListContract {
+
+    // 0. DESPLIEGUE: El administrador se fija al desplegar el contrato, en la misma transacción
+    // (así nadie puede adelantarse a inicializarlo). Después solo cambia con la acción
+    // programada 'AdminAction::SetAdmin', sujeta al timelock.
+    pub fn __constructor(env: Env, admin: Address) {
+        env.storage().instance().set(&ADMIN_KEY, &admin);
+    }
+
+    // 1. CREAR: Añadir nueva tarea y crear índice de propietario
+    pub fn add_task(env: Env, description: String, owner: Address) -> Result<u64, TaskError> {
+        Self::bump_ttl(&env);
+        // Validación de Seguridad: La dirección 'owner' debe firmar la transacción
+        owner.require_auth(); 
+
+        Self::create_task(&env, description, owner, TaskStatus::Pending)
+    }
+
+    // 2. LEER: Obtener tarea por ID
+    pub fn get_task_by_id(env: Env, task_id: u64) -> Option<Task> {
+        Self::bump_ttl(&env);
+        Self::load_task(&env, task_id)
+    }
+
+    // 3. LEER AVANZADO: Retorna todas las tareas (no eliminadas) de un propietario específico
+    // Esta función usa el índice que se creó en 'add_task'.
+    pub fn get_tasks_by_owner(env: Env, owner: Address) -> Vec<Task> {
+        Self::bump_ttl(&env);
+        Self::list_owner_tasks(&env, &owner)
+    }
+    
+    // 4. ACTUALIZAR: Concluir tarea
+    pub fn task_completed(env: Env, task_id: u64, caller: Address) -> Result<(), TaskError> {
+        Self::bump_ttl(&env);
+        let task = Self::load_task_for_update(&env, task_id, &caller, (task_id,).into_val(&env))?;
+
+        // Las tareas con compromiso solo se concluyen con 'complete_with_proof'
+        if env.storage().instance().has(&DataKey::CompletionCommitment(task_id)) {
+            return Err(TaskError::ProofRequired);
+        }
+
+        Self::complete_task(&env, task)
+    }
+
+    // 5. ACTUALIZAR: Modificar la descripción de una tarea pendiente (NUEVA FUNCIÓN)
+    pub fn update_task_description(env: Env, task_id: u64, caller: Address, new_description: String) -> Result<(), TaskError> {
+        Self::bump_ttl(&env);
+        // Validación 1: Solo el propietario original (su session key o un editor delegado vigente),
+        // sin congelamientos
+        let mut task = Self::load_task_for_edit(&env, task_id, &caller, (task_id, new_description.clone()).into_val(&env))?;
+        
+        // Validación 2: La nueva descripción debe ser válida (se guarda sin espacios en los extremos)
+        let new_description = Self::normalize_description(&env, &new_description)?;
+        
+        // Validación 3: Solo se pueden modificar tareas PENDIENTES
+        if task.status != TaskStatus::Pending {
+            return Err(TaskError::TaskAlreadyCompleted);
+        }
+
+        // Validación 4: Sin cambios no se escribe ni se emite evento
+        if new_description == task.description {
+            return Err(TaskError::NoChange);
+        }
+
+        task.description = new_description;
+
+        Self::save_task(&env, &mut task);
+        Self::emit_task_event(&env, symbol_short!("updated"), task_id);
+        Ok(())
+    }
+
+    // 6. ACTUALIZAR (Soft Delete): Marcar tarea como eliminada
+    pub fn task_deleted(env: Env, task_id: u64, caller: Address) -> Result<(), TaskError> {
+        Self::bump_ttl(&env);
+        let task = Self::load_task_for_update(&env, task_id, &caller, (task_id,).into_val(&env))?;
+        Self::soft_delete(&env, task)
+    }
+
+    // 7. FUNCIÓN AVANZADA: Transferir Propiedad
+    pub fn transfer_ownership(env: Env, task_id: u64, caller: Address, new_owner: Address) -> Result<(), TaskError> {
+        Self::bump_ttl(&env);
+        // Se mueven al nuevo propietario los contadores de tareas abiertas y de vencimientos, pero
+        // no los índices de propietario: los repara 'rebuild_owner_index' ('transfer_all' y
+        // 'reassign_task' sí los reescriben).
+        // Las session keys NO pueden transferir: solo el propietario real.
+        caller.require_auth_for_args((task_id, new_owner.clone()).into_val(&env));
+
+        let mut task = Self::load_task(&env, task_id).ok_or(TaskError::TaskNotFound)?;
+
+        if task.owner != caller {
+            return Err(TaskError::Unauthorized);
+        }
+        if new_owner == task.owner {
+            return Err(TaskError::SelfTransfer);
+        }
+
+        // Las tareas concluidas o en la papelera no cambian de manos (salvo que el despliegue lo permita)
+        let closed = matches!(task.status, TaskStatus::Completed | TaskStatus::Deleted);
+        if closed && !Self::is_closed_transferable(env.clone()) {
+            return Err(TaskError::TaskNotTransferable);
+        }
+
+        // Ni la tarea ni las cuentas implicadas pueden estar congeladas (ni la tarea bloqueada por otro)
+        Self::ensure_not_frozen(&env, task_id)?;
+        Self::ensure_not_locked(&env, task_id, &caller)?;
+        Self::ensure_account_active(&env, &caller)?;
+        Self::ensure_account_active(&env, &task.owner)?;
+
+        let previous_owner = task.owner.clone();
+        Self::move_open_counters(&env, &task, &new_owner);
+        task.owner = new_owner.clone();
+        
+        Self::save_task(&env, &mut task);
+        Self::emit_transfer_event(&env, symbol_short!("transfer"), task_id, previous_owner, new_owner);
+        Ok(())
+    }
+
+    // 8. LEER AVANZADO: Retorna todas las tareas pendientes y concluidas (excluye eliminadas, programadas
+    // y ocultadas por moderación)
+    // NOTA: Esta función itera sobre todos los IDs, no es eficiente para contratos con muchos datos.
+    // En modo público se niega (PaginationRequired) a partir de GET_ALL_MAX_IDS IDs; con el listado
+    // público desactivado solo puede llamarla el admin.
+    pub fn get_all(env: Env) -> Result<Vec<Task>, TaskError> {
+        Self::bump_ttl(&env);
+        let last_id = Self::get_next_task_id(&env);
+
+        if Self::is_public_enumeration(env.clone()) {
+            if last_id - 1 > GET_ALL_MAX_IDS {
+                return Err(TaskError::PaginationRequired);
+            }
+        } else {
+            Self::require_admin(&env)?;
+        }
+
+        let mut tasks = Vec::new(&env);
+        for id in 1..last_id {
+            if let Some(task) = Self::load_listable(&env, id) {
+                tasks.push_back(task);
+            }
+        }
+        Ok(tasks)
+    }
+
+    // 9. SESIONES: Registrar una session key temporal para el propietario
+    // Mientras no expire, la session key puede completar, editar y eliminar tareas
+    // del propietario sin que este tenga que firmar cada operación.
+    pub fn set_session_key(env: Env, owner: Address, session_key: Address, expires_at: u32) -> Result<(), TaskError> {
+        owner.require_auth();
+        Self::ensure_account_active(&env, &owner)?;
+
+        if session_key == owner || expires_at <= env.ledger().sequence() {
+            return Err(TaskError::InvalidSessionKey);
+        }
+
+        let session = SessionKey {
+            key: session_key,
+            expires_at,
+        };
+        env.storage().instance().set(&DataKey::SessionKey(owner), &session);
+        Ok(())
+    }
+
+    // 10. SESIONES: Revocar la session key del propietario antes de su expiración
+    pub fn revoke_session_key(env: Env, owner: Address) {
+        owner.require_auth();
+        env.storage().instance().remove(&DataKey::SessionKey(owner));
+    }
+
+    // 11. LEER: Session key registrada por un propietario (puede estar ya expirada)
+    pub fn get_session_key(env: Env, owner: Address) -> Option<SessionKey> {
+        env.storage().instance().get(&DataKey::SessionKey(owner))
+    }
+
+    // 13. LEER: Administrador actual (None si aún no se ha inicializado)
+    pub fn get_admin(env: Env) -> Option<Address> {
+        env.storage().instance().get(&ADMIN_KEY)
+    }
+
+    // 14. CONGELAR: Bloquear todas las modificaciones de una tarea (disputas, cumplimiento)
+    // Puede hacerlo el propietario, el admin o un Pauser.
+    pub fn freeze_task(env: Env, task_id: u64, caller: Address) -> Result<(), TaskError> {
+        Self::bump_ttl(&env);
+        caller.require_auth_for_args((task_id,).into_val(&env));
+
+        let task = Self::load_task(&env, task_id).ok_or(TaskError::TaskNotFound)?;
+
+        if task.owner != caller && !Self::is_admin_or(&env, Role::Pauser, &caller) {
+            return Err(TaskError::Unauthorized);
+        }
+
+        // No se puede sobrescribir un congelamiento existente (p.ej. uno del admin)
+        Self::ensure_not_frozen(&env, task_id)?;
+
+        env.storage().instance().set(&DataKey::FrozenTask(task_id), &caller);
+        Self::emit_task_event(&env, symbol_short!("frozen"), task_id);
+        Ok(())
+    }
+
+    // 15. CONGELAR: Desbloquear una tarea congelada
+    // El admin o un Pauser siempre pueden; el propietario solo si fue él quien la congeló.
+    pub fn unfreeze_task(env: Env, task_id: u64, caller: Address) -> Result<(), TaskError> {
+        caller.require_auth_for_args((task_id,).into_val(&env));
+
+        let frozen_by: Address = match env.storage().instance().get(&DataKey::FrozenTask(task_id)) {
+            Some(addr) => addr,
+            None => return Ok(()),
+        };
+
+        if frozen_by != caller && !Self::is_admin_or(&env, Role::Pauser, &caller) {
+            return Err(TaskError::Unauthorized);
+        }
+
+        env.storage().instance().remove(&DataKey::FrozenTask(task_id));
+        Self::emit_task_event(&env, symbol_short!("unfrozen"), task_id);
+        Ok(())
+    }
+
+    // 16. LEER: Indica si una tarea está congelada
+    pub fn is_task_frozen(env: Env, task_id: u64) -> bool {
+        env.storage().instance().has(&DataKey::FrozenTask(task_id))
+    }
+
+
+    // 17. ADMIN: Congelar una cuenta comprometida o abusiva
+    // La cuenta no podrá crear ni modificar tareas, pero sus datos siguen siendo legibles.
+    pub fn freeze_account(env: Env, addr: Address) -> Result<(), TaskError> {
+        Self::require_admin(&env)?;
+        env.storage().instance().set(&DataKey::FrozenAccount(addr), &true);
+        Ok(())
+    }
+
+    // 18. ADMIN: Descongelar una cuenta
+    pub fn unfreeze_account(env: Env, addr: Address) -> Result<(), TaskError> {
+        Self::require_admin(&env)?;
+        env.storage().instance().remove(&DataKey::FrozenAccount(addr));
+        Ok(())
+    }
+
+    // 19. LEER: Indica si una cuenta está congelada
+    pub fn is_account_frozen(env: Env, addr: Address) -> bool {
+        env.storage().instance().has(&DataKey::FrozenAccount(addr))
+    }
+
+
+    // 20. FUNCIÓN AVANZADA: Transferir todas las tareas vivas de un propietario (rotación de wallet)
+    // Mueve como máximo 'limit' tareas por llamada y reescribe ambos índices de propietario;
+    // se repite hasta que retorne 0. Las tareas eliminadas, congeladas o bloqueadas por otra
+    // dirección se quedan donde están, igual que las concluidas si el despliegue no permite
+    // transferirlas (mismas reglas que 'transfer_ownership').
+    pub fn transfer_all(env: Env, owner: Address, new_owner: Address, limit: u32) -> Result<u32, TaskError> {
+        Self::bump_ttl(&env);
+        owner.require_auth();
+        Self::ensure_account_active(&env, &owner)?;
+
+        if new_owner == owner || limit == 0 {
+            return Err(TaskError::InvalidTaskData);
+        }
+
+        let owner_tasks: Vec<u64> = Self::load_ids(&env, &owner);
+        let mut new_owner_tasks: Vec<u64> = Self::load_ids(&env, &new_owner);
+        let mut remaining: Vec<u64> = Vec::new(&env);
+        let mut moved: u32 = 0;
+        let closed_transferable = Self::is_closed_transferable(env.clone());
+
+        for task_id in owner_tasks.iter() {
+            if moved < limit && !Self::is_task_frozen(env.clone(), task_id) && Self::ensure_not_locked(&env, task_id, &owner).is_ok() {
+                if let Some(mut task) = Self::load_task(&env, task_id) {
+                    let transferable = match task.status {
+                        TaskStatus::Deleted => false,
+                        TaskStatus::Completed => closed_transferable,
+                        _ => true,
+                    };
+                    if task.owner == owner && transferable {
+                        Self::move_open_counters(&env, &task, &new_owner);
+                        task.owner = new_owner.clone();
+                        Self::save_task(&env, &mut task);
+                        Self::emit_transfer_event(&env, symbol_short!("transfer"), task_id, owner.clone(), new_owner.clone());
+                        new_owner_tasks.push_back(task_id);
+                        moved += 1;
+                        continue;
+                    }
+                }
+            }
+            remaining.push_back(task_id);
+        }
+
+        env.storage().instance().set(&owner, &remaining);
+        env.storage().instance().set(&new_owner, &new_owner_tasks);
+        Ok(moved)
+    }
+
+
+    // 21. LEER: Digest determinista de las tareas vivas de un propietario
+    // SHA-256 sobre el XDR de cada tarea (id, estado, marcas de tiempo...) en el orden del
+    // índice, para que las cachés off-chain verifiquen si están al día antes de recargar todo.
+    // Incluye las tareas programadas y las del backlog, aunque 'get_tasks_by_owner' no las liste.
+    pub fn get_owner_digest(env: Env, owner: Address) -> BytesN<32> {
+        Self::bump_ttl(&env);
+        let mut data = Bytes::new(&env);
+
+        for task_id in Self::load_ids(&env, &owner).iter() {
+            if let Some(task) = Self::load_task(&env, task_id) {
+                // Ignorar entradas del índice que ya pertenecen a otro propietario
+                if task.owner == owner && task.status != TaskStatus::Deleted {
+                    data.append(&task.to_xdr(&env));
+                }
+            }
+        }
+        env.crypto().sha256(&data).into()
+    }
+
+
+    // 22. LEER: Raíz Merkle sobre todos los registros de tareas (verificación ligera)
+    // Hoja i = SHA-256(0x00 || XDR(tarea con id i+1)), o 32 bytes a cero si el ID no existe.
+    // Las hojas se rellenan con ceros hasta una potencia de 2; nodo = SHA-256(0x01 || izq || der).
+    // NOTA: Se calcula bajo demanda recorriendo todos los IDs; como 'get_all', se niega
+    // (PaginationRequired) a partir de GET_ALL_MAX_IDS IDs: usar 'get_state_root_page'.
+    pub fn get_state_root(env: Env) -> Result<BytesN<32>, TaskError> {
+        Self::bump_ttl(&env);
+        let last_id = Self::get_next_task_id(&env);
+        if last_id - 1 > GET_ALL_MAX_IDS {
+            return Err(TaskError::PaginationRequired);
+        }
+
+        let mut frontier = Vec::new(&env);
+        for id in 1..last_id {
+            Self::merkle_push(&env, &mut frontier, id - 1, Self::merkle_leaf(&env, id));
+        }
+        Ok(Self::merkle_finish(&env, &frontier, last_id - 1))
+    }
+
+    // 23. LEER: Prueba Merkle de una tarea (hermanos desde la hoja hasta la raíz)
+    // En cada nivel, si el índice actual es par el hermano va a la derecha, si no a la izquierda.
+    pub fn get_proof(env: Env, task_id: u64) -> Result<Vec<BytesN<32>>, TaskError> {
+        Self::bump_ttl(&env);
+        let last_id = Self::get_next_task_id(&env);
+        if task_id == 0 || task_id >= last_id {
+            return Err(TaskError::TaskNotFound);
+        }
+        if last_id - 1 > GET_ALL_MAX_IDS {
+            return Err(TaskError::PaginationRequired);
+        }
+
+        let mut proof = Vec::new(&env);
+        let mut level = Self::merkle_leaves(&env);
+        let mut index = u32::try_from(task_id - 1).map_err(|_| TaskError::TaskNotFound)?;
+
+        while level.len() > 1 {
+            proof.push_back(level.get(index ^ 1).unwrap());
+            level = Self::merkle_next_level(&env, &level);
+            index /= 2;
+        }
+        Ok(proof)
+    }
+
+
+    // 24. MANTENIMIENTO: Comprobar el índice de un propietario contra los registros reales
+    // Detecta la deriva del índice (p.ej. tras 'transfer_ownership') en el rango [from_id, to_id],
+    // el mismo que repara 'rebuild_owner_index'. Solo lee: no migra los registros antiguos.
+    // NOTA: Se niega (PaginationRequired) con rangos de más de GET_ALL_MAX_IDS IDs.
+    pub fn verify_integrity(env: Env, owner: Address, from_id: u64, to_id: u64) -> Result<IntegrityReport, TaskError> {
+        Self::bump_ttl(&env);
+        if from_id == 0 || to_id < from_id {
+            return Err(TaskError::InvalidTaskData);
+        }
+        if to_id - from_id >= GET_ALL_MAX_IDS {
+            return Err(TaskError::PaginationRequired);
+        }
+        let mut report = IntegrityReport {
+            missing_ids: Vec::new(&env),
+            stale_ids: Vec::new(&env),
+            mismatched_ids: Vec::new(&env),
+        };
+        let index: Vec<u64> = Self::load_ids(&env, &owner);
+        let mut seen: Vec<u64> = Vec::new(&env);
+
+        // 1. Revisar cada entrada del índice dentro del rango
+        for task_id in index.iter() {
+            if task_id < from_id || task_id > to_id {
+                continue;
+            }
+            if seen.contains(task_id) {
+                report.stale_ids.push_back(task_id);
+                continue;
+            }
+            seen.push_back(task_id);
+
+            match Self::peek_task(&env, task_id) {
+                Some(task) => {
+                    if task.owner != owner {
+                        report.mismatched_ids.push_back(task_id);
+                    }
+                }
+                None => report.stale_ids.push_back(task_id),
+            }
+        }
+
+        // 2. Buscar tareas del propietario del rango que falten en el índice
+        for id in from_id..=to_id.min(Self::get_next_task_id(&env).saturating_sub(1)) {
+            if let Some(task) = Self::peek_task(&env, id) {
+                if task.owner == owner && !seen.contains(id) {
+                    report.missing_ids.push_back(id);
+                }
+            }
+        }
+        Ok(report)
+    }
+
+
+    // 25. MANTENIMIENTO: Reconstruir el índice de un propietario para un rango de IDs [from_id, to_id]
+    // Elimina del índice las entradas del rango que no le pertenecen (o duplicadas) y añade las
+    // tareas suyas que falten. No requiere firma: el resultado depende solo de los registros.
+    // Retorna el número de correcciones aplicadas.
+    pub fn rebuild_owner_index(env: Env, owner: Address, from_id: u64, to_id: u64) -> Result<u32, TaskError> {
+        Self::bump_ttl(&env);
+        if from_id == 0 || to_id < from_id {
+            return Err(TaskError::InvalidTaskData);
+        }
+        let last_id = Self::get_next_task_id(&env);
+        let index: Vec<u64> = Self::load_ids(&env, &owner);
+        let mut rebuilt: Vec<u64> = Vec::new(&env);
+        let mut fixes: u32 = 0;
+
+        // 1. Conservar las entradas válidas (las de fuera del rango no se tocan)
+        for task_id in index.iter() {
+            let in_range = task_id >= from_id && task_id <= to_id;
+            let valid = match Self::load_task(&env, task_id) {
+                Some(task) => task.owner == owner && !rebuilt.contains(task_id),
+                None => false,
+            };
+            if in_range && !valid {
+                fixes += 1;
+            } else {
+                rebuilt.push_back(task_id);
+            }
+        }
+
+        // 2. Añadir las tareas del rango que pertenecen al propietario y faltan
+        for id in from_id..=to_id {
+            if id >= last_id {
+                break;
+            }
+            if let Some(task) = Self::load_task(&env, id) {
+                if task.owner == owner && !rebuilt.contains(id) {
+                    rebuilt.push_back(id);
+                    fixes += 1;
+                }
+            }
+        }
+
+        if fixes > 0 {
+            env.storage().instance().set(&owner, &rebuilt);
+        }
+        Ok(fixes)
+    }
+
+
+    // 26. ACTUALIZAR: Cambiar la prioridad de una tarea pendiente
+    pub fn set_task_priority(env: Env, task_id: u64, caller: Address, priority: TaskPriority) -> Result<(), TaskError> {
+        Self::bump_ttl(&env);
+        let mut task = Self::load_task_for_update(&env, task_id, &caller, (task_id, priority.clone()).into_val(&env))?;
+
+        if task.status != TaskStatus::Pending {
+            return Err(TaskError::TaskAlreadyCompleted);
+        }
+
+        task.priority = priority;
+
+        // Un cambio manual de prioridad reinicia el envejecimiento
+        if Self::get_priority_aging(env.clone(), task.owner.clone()).is_some() {
+            env.storage().instance().set(&DataKey::AgedAt(task_id), &env.ledger().timestamp());
+        }
+
+        Self::save_task(&env, &mut task);
+        Self::emit_task_event(&env, symbol_short!("priority"), task_id);
+        Ok(())
+    }
+
+    // 27. ACTUALIZAR: Fijar (o quitar con None) la fecha límite de una tarea pendiente
+    pub fn set_task_due_date(env: Env, task_id: u64, caller: Address, due_date: Option<u64>) -> Result<(), TaskError> {
+        Self::bump_ttl(&env);
+        let mut task = Self::load_task_for_update(&env, task_id, &caller, (task_id, due_date).into_val(&env))?;
+
+        if task.status != TaskStatus::Pending {
+            return Err(TaskError::TaskAlreadyCompleted);
+        }
+
+        // La fecha límite debe estar en el futuro
+        if let Some(due) = due_date {
+            if due <= env.ledger().timestamp() {
+                return Err(TaskError::InvalidTaskData);
+            }
+        }
+
+        Self::track_due(&env, &task, false);
+        task.due_date = due_date;
+        Self::track_due(&env, &task, true);
+
+        Self::save_task(&env, &mut task);
+        Self::emit_task_event(&env, symbol_short!("due_date"), task_id);
+        Ok(())
+    }
+
+    // 28. MIGRACIÓN: Versión del esquema de almacenamiento
+    // Un contrato con tareas pero sin versión guardada es un despliegue v1.
+    pub fn get_storage_version(env: Env) -> u32 {
+        match env.storage().instance().get(&STORAGE_VERSION_KEY) {
+            Some(version) => version,
+            None if env.storage().instance().has(&NEXT_ID_KEY) => 1,
+            None => STORAGE_VERSION,
+        }
+    }
+
+    // 29. MIGRACIÓN: Reescribir una tarea v1 o v2 con el esquema actual
+    // Pasa su ID a u64 junto con sus entradas auxiliares. Las tareas sin migrar se migran al
+    // leerlas, pero hasta entonces sus entradas auxiliares se leen vacías: conviene lanzar
+    // 'migrate_batch' justo tras la actualización. Se pierden:
+    //  - la instantánea para deshacer
+    //  - los permisos de edición delegados
+    //  - los bloqueos temporales
+    //  - las acciones 'ReassignTask' ya programadas (hay que volver a programarlas)
+    pub fn migrate_task(env: Env, task_id: u64) -> Result<(), TaskError> {
+        if Self::get_storage_version(env.clone()) >= STORAGE_VERSION {
+            return Err(TaskError::MigrationNotNeeded);
+        }
+
+        if !Self::migrate_one(&env, task_id) {
+            return Err(TaskError::TaskNotFound);
+        }
+        Ok(())
+    }
+
+    // 30. MIGRACIÓN: Reescribir las tareas v1 o v2 del rango [from, to]
+    // Los lotes consecutivos desde el ID 1 avanzan un cursor; cuando cubre todos los IDs,
+    // se guarda la versión actual y las migraciones quedan deshabilitadas.
+    // Retorna el número de tareas migradas en este lote.
+    pub fn migrate_batch(env: Env, from: u64, to: u64) -> Result<u32, TaskError> {
+        if Self::get_storage_version(env.clone()) >= STORAGE_VERSION {
+            return Err(TaskError::MigrationNotNeeded);
+        }
+        if from == 0 || to < from {
+            return Err(TaskError::InvalidTaskData);
+        }
+
+        let last_id = Self::get_next_task_id(&env);
+        let mut migrated: u32 = 0;
+
+        for id in from..=to {
+            if id >= last_id {
+                break;
+            }
+            if Self::migrate_one(&env, id) {
+                migrated += 1;
+            }
+        }
+
+        // Avanzar el cursor si este lote continúa donde terminó el anterior
+        let cursor: u64 = Self::load_id(&env, &MIGRATION_CURSOR_KEY).unwrap_or(1);
+        if from <= cursor && to >= cursor {
+            let new_cursor = to.saturating_add(1);
+            if new_cursor >= last_id {
+                env.storage().instance().set(&STORAGE_VERSION_KEY, &STORAGE_VERSION);
+                env.storage().instance().remove(&MIGRATION_CURSOR_KEY);
+            } else {
+                env.storage().instance().set(&MIGRATION_CURSOR_KEY, &new_cursor);
+            }
+        }
+        Ok(migrated)
+    }
+
+
+    // 31. CREAR: Añadir una tarea programada ("crear ahora, empezar la semana que viene")
+    // No aparece en los listados hasta 'scheduled_for'; a partir de entonces se lee como Pending
+    // aunque ningún keeper haya llamado todavía a 'activate_scheduled'.
+    pub fn add_scheduled_task(env: Env, description: String, owner: Address, scheduled_for: u64) -> Result<u64, TaskError> {
+        Self::bump_ttl(&env);
+        owner.require_auth();
+
+        if scheduled_for <= env.ledger().timestamp() {
+            return Err(TaskError::InvalidTaskData);
+        }
+
+        let task_id = Self::create_task(&env, description, owner, TaskStatus::Scheduled)?;
+        env.storage().instance().set(&DataKey::ScheduledFor(task_id), &scheduled_for);
+
+        let mut scheduled: Vec<u64> = Self::load_ids(&env, &SCHEDULED_KEY);
+        scheduled.push_back(task_id);
+        env.storage().instance().set(&SCHEDULED_KEY, &scheduled);
+
+        Ok(task_id)
+    }
+
+    // 32. LEER: Momento de activación de una tarea programada
+    pub fn get_scheduled_for(env: Env, task_id: u64) -> Option<u64> {
+        env.storage().instance().get(&DataKey::ScheduledFor(task_id))
+    }
+
+    // 33. KEEPER: Pasar a Pending (en almacenamiento) hasta 'limit' tareas programadas ya vencidas
+    // Cualquiera puede llamarla. Retorna el número de tareas activadas.
+    pub fn activate_scheduled(env: Env, limit: u32) -> u32 {
+        Self::bump_ttl(&env);
+        let scheduled: Vec<u64> = Self::load_ids(&env, &SCHEDULED_KEY);
+        let mut remaining: Vec<u64> = Vec::new(&env);
+        let mut activated: u32 = 0;
+
+        for task_id in scheduled.iter() {
+            if activated < limit {
+                match Self::load_stored_task(&env, task_id) {
+                    Some(mut task) if task.status == TaskStatus::Scheduled => {
+                        if Self::activate_if_due(&env, task.clone()).status == TaskStatus::Pending {
+                            if Self::transition(&mut task, TaskStatus::Pending).is_err() {
+                                continue;
+                            }
+                            Self::save_task_unattended(&env, &mut task);
+                            Self::emit_status_event(&env, symbol_short!("activated"), task_id, TaskStatus::Scheduled, TaskStatus::Pending);
+                            env.storage().instance().remove(&DataKey::ScheduledFor(task_id));
+                            activated += 1;
+                            continue;
+                        }
+                    }
+                    // Ya no está programada (completada, eliminada...): limpiar la entrada
+                    _ => {
+                        env.storage().instance().remove(&DataKey::ScheduledFor(task_id));
+                        continue;
+                    }
+                }
+            }
+            remaining.push_back(task_id);
+        }
+
+        env.storage().instance().set(&SCHEDULED_KEY, &remaining);
+        activated
+    }
+
+
+    // 34. CONFIGURAR: Envejecimiento de prioridad (opt-in por propietario)
+    // Una tarea pendiente sube un nivel de prioridad por cada 'max_age' segundos sin atenderse.
+    // 'max_age' = 0 lo desactiva.
+    pub fn set_priority_aging(env: Env, owner: Address, max_age: u64) -> Result<(), TaskError> {
+        owner.require_auth();
+        Self::ensure_account_active(&env, &owner)?;
+
+        if max_age == 0 {
+            env.storage().instance().remove(&DataKey::PriorityAging(owner));
+        } else {
+            env.storage().instance().set(&DataKey::PriorityAging(owner), &max_age);
+        }
+        Ok(())
+    }
+
+    // 35. LEER: Configuración de envejecimiento de un propietario (None = desactivado)
+    pub fn get_priority_aging(env: Env, owner: Address) -> Option<u64> {
+        env.storage().instance().get(&DataKey::PriorityAging(owner))
+    }
+
+    // 36. LEER: Prioridad efectiva de una tarea, aplicando el envejecimiento pendiente de persistir
+    pub fn get_effective_priority(env: Env, task_id: u64) -> Option<TaskPriority> {
+        Self::bump_ttl(&env);
+        let task = Self::load_task(&env, task_id)?;
+
+        if task.status == TaskStatus::Pending {
+            if let Some(max_age) = Self::get_priority_aging(env.clone(), task.owner.clone()) {
+                let steps = Self::aging_steps(&env, &task, max_age);
+                return Some(Self::raise_priority(task.priority, steps));
+            }
+        }
+        Some(task.priority)
+    }
+
+    // 37. KEEPER: Persistir el envejecimiento de hasta 'limit' tareas pendientes de un propietario
+    // Cualquiera puede llamarla. Retorna el número de tareas escaladas.
+    pub fn escalate_stale(env: Env, owner: Address, limit: u32) -> u32 {
+        Self::bump_ttl(&env);
+        let max_age = match Self::get_priority_aging(env.clone(), owner.clone()) {
+            Some(max_age) => max_age,
+            None => return 0,
+        };
+        let index: Vec<u64> = Self::load_ids(&env, &owner);
+        let mut escalated: u32 = 0;
+
+        for task_id in index.iter() {
+            if escalated >= limit {
+                break;
+            }
+            if Self::is_task_frozen(env.clone(), task_id) {
+                continue;
+            }
+            if let Some(mut task) = Self::load_task(&env, task_id) {
+                if task.owner != owner || task.status != TaskStatus::Pending || task.priority == TaskPriority::Critical {
+                    continue;
+                }
+                let steps = Self::aging_steps(&env, &task, max_age);
+                if steps > 0 {
+                    let anchor: u64 = env.storage().instance().get(&DataKey::AgedAt(task_id)).unwrap_or(task.timestamp);
+                    task.priority = Self::raise_priority(task.priority, steps);
+                    Self::save_task_unattended(&env, &mut task);
+                    Self::emit_task_event(&env, symbol_short!("priority"), task_id);
+                    env.storage().instance().set(&DataKey::AgedAt(task_id), &(anchor + steps * max_age));
+                    escalated += 1;
+                }
+            }
+        }
+        escalated
+    }
+
+
+    // 38. ACTUALIZAR: Bloquear una tarea pendiente indicando el motivo
+    pub fn block_task(env: Env, task_id: u64, caller: Address, reason: String) -> Result<(), TaskError> {
+        Self::bump_ttl(&env);
+        let mut task = Self::load_task_for_update(&env, task_id, &caller, (task_id, reason.clone()).into_val(&env))?;
+
+        if reason.is_empty() {
+            return Err(TaskError::InvalidTaskData);
+        }
+        if task.status != TaskStatus::Pending {
+            return Err(TaskError::TaskAlreadyCompleted);
+        }
+
+        Self::transition(&mut task, TaskStatus::Blocked)?;
+
+        Self::save_task(&env, &mut task);
+        Self::emit_status_event(&env, symbol_short!("blocked"), task_id, TaskStatus::Pending, TaskStatus::Blocked);
+        env.storage().instance().set(&DataKey::BlockedReason(task_id), &reason);
+        Self::discard_undo(&env, task_id);
+        Ok(())
+    }
+
+    // 39. ACTUALIZAR: Desbloquear una tarea (vuelve a Pending)
+    pub fn unblock_task(env: Env, task_id: u64, caller: Address) -> Result<(), TaskError> {
+        Self::bump_ttl(&env);
+        let mut task = Self::load_task_for_update(&env, task_id, &caller, (task_id,).into_val(&env))?;
+
+        if task.status != TaskStatus::Blocked {
+            return Err(TaskError::InvalidTaskData);
+        }
+
+        Self::transition(&mut task, TaskStatus::Pending)?;
+
+        Self::save_task(&env, &mut task);
+        Self::emit_status_event(&env, symbol_short!("unblocked"), task_id, TaskStatus::Blocked, TaskStatus::Pending);
+        env.storage().instance().remove(&DataKey::BlockedReason(task_id));
+        Self::discard_undo(&env, task_id);
+        Ok(())
+    }
+
+    // 40. LEER: Detalle de una tarea (incluye el motivo de bloqueo)
+    pub fn get_task_detail(env: Env, task_id: u64) -> Option<TaskDetail> {
+        Self::bump_ttl(&env);
+        let task = Self::load_task(&env, task_id)?;
+
+        let blocked_reason = if task.status == TaskStatus::Blocked {
+            env.storage().instance().get(&DataKey::BlockedReason(task_id))
+        } else {
+            None
+        };
+
+        let description_encrypted = env.storage().instance().get(&DataKey::EncryptedDescription(task_id));
+        let color = env.storage().instance().get(&OrgKey::TaskColor(task_id));
+        let icon = env.storage().instance().get(&OrgKey::TaskIcon(task_id));
+
+        Some(TaskDetail { task, blocked_reason, description_encrypted, color, icon })
+    }
+
+
+    // 41. ACTUALIZAR: Cancelar una tarea que no se va a hacer
+    // A diferencia del soft-delete, la tarea cancelada sigue en los listados y cuenta como no hecha.
+    pub fn cancel_task(env: Env, task_id: u64, caller: Address) -> Result<(), TaskError> {
+        Self::bump_ttl(&env);
+        let mut task = Self::load_task_for_update(&env, task_id, &caller, (task_id,).into_val(&env))?;
+
+        match task.status {
+            TaskStatus::Completed => return Err(TaskError::TaskAlreadyCompleted),
+            TaskStatus::Cancelled => return Err(TaskError::TaskCancelled),
+            TaskStatus::Deleted => return Err(TaskError::TaskNotFound),
+            _ => {}
+        }
+
+        let old_status = Self::transition(&mut task, TaskStatus::Cancelled)?;
+        if Self::is_open(&old_status) {
+            Self::record_day_activity(&env, &task.owner, false, false);
+            Self::track_due(&env, &task, false);
+        }
+
+        Self::count_stat(&env, |stats| stats.cancelled += 1);
+
+        Self::save_task(&env, &mut task);
+        // Sin el motivo del bloqueo, la versión anterior ya no se podría restaurar entera
+        if old_status == TaskStatus::Blocked {
+            Self::discard_undo(&env, task_id);
+        }
+        Self::emit_status_event(&env, symbol_short!("cancelled"), task_id, old_status, TaskStatus::Cancelled);
+        env.storage().instance().remove(&DataKey::BlockedReason(task_id));
+        Ok(())
+    }
+
+
+    // 42. DELEGAR: Conceder a 'editor' permiso para editar la descripción hasta 'expires_at'
+    // El permiso caduca solo, comparando con el timestamp del ledger (sin transacción de limpieza).
+    pub fn grant_edit(env: Env, task_id: u64, owner: Address, editor: Address, expires_at: u64) -> Result<(), TaskError> {
+        Self::bump_ttl(&env);
+        let task = Self::load_task_for_update(&env, task_id, &owner, (task_id, editor.clone(), expires_at).into_val(&env))?;
+
+        // Solo el propietario real (no su session key) puede delegar
+        if task.owner != owner {
+            return Err(TaskError::Unauthorized);
+        }
+        if editor == owner || expires_at <= env.ledger().timestamp() {
+            return Err(TaskError::InvalidTaskData);
+        }
+
+        env.storage().instance().set(&DataKey::EditGrant(task_id, editor), &expires_at);
+        Ok(())
+    }
+
+    // 43. DELEGAR: Retirar el permiso de edición antes de que caduque
+    pub fn revoke_edit(env: Env, task_id: u64, owner: Address, editor: Address) -> Result<(), TaskError> {
+        Self::bump_ttl(&env);
+        owner.require_auth_for_args((task_id, editor.clone()).into_val(&env));
+
+        let task = Self::load_task(&env, task_id).ok_or(TaskError::TaskNotFound)?;
+        if task.owner != owner {
+            return Err(TaskError::Unauthorized);
+        }
+
+        env.storage().instance().remove(&DataKey::EditGrant(task_id, editor));
+        Ok(())
+    }
+
+    // 44. LEER: Expiración del permiso de edición de 'editor' (puede estar ya caducado)
+    pub fn get_edit_grant(env: Env, task_id: u64, editor: Address) -> Option<u64> {
+        env.storage().instance().get(&DataKey::EditGrant(task_id, editor))
+    }
+
+
+    // 45. LEER: "Decide por mí" - una tarea pendiente al azar del propietario
+    // Usa el PRNG del entorno: sirve para sugerencias, no para nada que requiera imprevisibilidad.
+    pub fn pick_random_pending(env: Env, owner: Address) -> Option<Task> {
+        Self::bump_ttl(&env);
+        let mut pending: Vec<Task> = Vec::new(&env);
+
+        for task in Self::list_owner_tasks(&env, &owner).iter() {
+            if task.owner == owner && task.status == TaskStatus::Pending {
+                pending.push_back(task);
+            }
+        }
+
+        if pending.is_empty() {
+            return None;
+        }
+        let index = env.prng().gen_range::<u64>(0..pending.len() as u64) as u32;
+        pending.get(index)
+    }
+
+
+    // 46. POMODORO: Empezar una sesión de concentración de 'duration' segundos sobre una tarea pendiente
+    pub fn start_pomodoro(env: Env, task_id: u64, caller: Address, duration: u64) -> Result<(), TaskError> {
+        Self::bump_ttl(&env);
+        let task = Self::load_task_for_update(&env, task_id, &caller, (task_id, duration).into_val(&env))?;
+
+        if task.status != TaskStatus::Pending {
+            return Err(TaskError::TaskAlreadyCompleted);
+        }
+        if duration == 0 || duration > MAX_POMODORO_SECS {
+            return Err(TaskError::InvalidTaskData);
+        }
+        if env.storage().instance().has(&DataKey::ActivePomodoro(task_id)) {
+            return Err(TaskError::PomodoroInProgress);
+        }
+
+        let pomodoro = Pomodoro {
+            started_at: env.ledger().timestamp(),
+            duration,
+        };
+        env.storage().instance().set(&DataKey::ActivePomodoro(task_id), &pomodoro);
+        Ok(())
+    }
+
+    // 47. POMODORO: Cerrar la sesión una vez cumplida su duración y acumular estadísticas
+    pub fn complete_pomodoro(env: Env, task_id: u64, caller: Address) -> Result<(), TaskError> {
+        Self::bump_ttl(&env);
+        let task = Self::load_task_for_update(&env, task_id, &caller, (task_id,).into_val(&env))?;
+
+        let pomodoro: Pomodoro = env
+            .storage()
+            .instance()
+            .get(&DataKey::ActivePomodoro(task_id))
+            .ok_or(TaskError::NoActivePomodoro)?;
+
+        if env.ledger().timestamp() < pomodoro.started_at.saturating_add(pomodoro.duration) {
+            return Err(TaskError::PomodoroInProgress);
+        }
+
+        env.storage().instance().remove(&DataKey::ActivePomodoro(task_id));
+        Self::add_focus(&env, &DataKey::TaskFocus(task_id), pomodoro.duration);
+        Self::add_focus(&env, &DataKey::OwnerFocus(task.owner), pomodoro.duration);
+        Ok(())
+    }
+
+    // 48. LEER: Estadísticas de concentración de un propietario
+    pub fn get_focus_stats(env: Env, owner: Address) -> FocusStats {
+        Self::read_focus(&env, &DataKey::OwnerFocus(owner))
+    }
+
+    // 49. LEER: Estadísticas de concentración de una tarea
+    pub fn get_task_focus_stats(env: Env, task_id: u64) -> FocusStats {
+        Self::read_focus(&env, &DataKey::TaskFocus(task_id))
+    }
+
+
+    // 50. HÁBITOS: Crear una tarea en modo hábito (se marca una vez al día, nunca se "termina")
+    pub fn add_habit(env: Env, description: String, owner: Address) -> Result<u64, TaskError> {
+        Self::bump_ttl(&env);
+        owner.require_auth();
+
+        let habit_id = Self::create_task(&env, description, owner, TaskStatus::Pending)?;
+        let state = HabitState {
+            current_streak: 0,
+            best_streak: 0,
+            total_check_ins: 0,
+            last_check_in_day: None,
+        };
+        env.storage().instance().set(&DataKey::Habit(habit_id), &state);
+        Ok(habit_id)
+    }
+
+    // 51. HÁBITOS: Marcar el hábito de hoy (firma el propietario)
+    // El día se calcula con el timestamp del ledger y el desfase horario del propietario;
+    // un check-in el día siguiente al anterior alarga la racha, si no, la reinicia.
+    pub fn check_in(env: Env, habit_id: u64) -> Result<HabitState, TaskError> {
+        Self::bump_ttl(&env);
+        let owner = Self::load_task(&env, habit_id).ok_or(TaskError::TaskNotFound)?.owner;
+        let task = Self::load_task_for_update(&env, habit_id, &owner, (habit_id,).into_val(&env))?;
+
+        let mut state: HabitState = env
+            .storage()
+            .instance()
+            .get(&DataKey::Habit(habit_id))
+            .ok_or(TaskError::NotAHabit)?;
+
+        if task.status != TaskStatus::Pending {
+            return Err(TaskError::TaskAlreadyCompleted);
+        }
+
+        let today = Self::local_day(&env, &owner);
+        match state.last_check_in_day {
+            Some(day) if day == today => return Err(TaskError::AlreadyCheckedIn),
+            Some(day) if day + 1 == today => state.current_streak += 1,
+            _ => state.current_streak = 1,
+        }
+        if state.current_streak > state.best_streak {
+            state.best_streak = state.current_streak;
+        }
+        state.total_check_ins += 1;
+        state.last_check_in_day = Some(today);
+
+        env.storage().instance().set(&DataKey::Habit(habit_id), &state);
+        Self::emit_task_event(&env, symbol_short!("checkin"), habit_id);
+        Ok(state)
+    }
+
+    // 52. LEER: Estado de un hábito (None si la tarea no es un hábito)
+    pub fn get_habit(env: Env, habit_id: u64) -> Option<HabitState> {
+        env.storage().instance().get(&DataKey::Habit(habit_id))
+    }
+
+    // 53. CONFIGURAR: Desfase horario del propietario respecto a UTC, en segundos
+    pub fn set_utc_offset(env: Env, owner: Address, offset_secs: i32) -> Result<(), TaskError> {
+        owner.require_auth();
+
+        if !(-MAX_UTC_OFFSET..=MAX_UTC_OFFSET).contains(&offset_secs) {
+            return Err(TaskError::InvalidTaskData);
+        }
+
+        env.storage().instance().set(&DataKey::UtcOffset(owner), &offset_secs);
+        Ok(())
+    }
+
+    // 54. LEER: Desfase horario del propietario (0 = UTC)
+    pub fn get_utc_offset(env: Env, owner: Address) -> i32 {
+        env.storage().instance().get(&DataKey::UtcOffset(owner)).unwrap_or(0)
+    }
+
+
+    // 55. OBJETIVOS: Crear un objetivo de 'target_count' tareas completadas antes de 'deadline'
+    pub fn create_goal(env: Env, owner: Address, name: String, target_count: u32, deadline: u64) -> Result<u32, TaskError> {
+        owner.require_auth();
+        Self::ensure_account_active(&env, &owner)?;
+
+        if name.is_empty() || target_count == 0 || deadline <= env.ledger().timestamp() {
+            return Err(TaskError::InvalidTaskData);
+        }
+
+        let goal_id: u32 = env.storage().instance().get(&NEXT_GOAL_ID_KEY).unwrap_or(1);
+        let goal = Goal {
+            id: goal_id,
+            owner: owner.clone(),
+            name,
+            target_count,
+            deadline,
+            progress: 0,
+        };
+        env.storage().instance().set(&DataKey::Goal(goal_id), &goal);
+
+        let mut owner_goals: Vec<u32> = env.storage().instance().get(&DataKey::OwnerGoals(owner.clone())).unwrap_or(Vec::new(&env));
+        owner_goals.push_back(goal_id);
+        env.storage().instance().set(&DataKey::OwnerGoals(owner), &owner_goals);
+
+        env.storage().instance().set(&NEXT_GOAL_ID_KEY, &Self::checked_next(goal_id)?);
+        Ok(goal_id)
+    }
+
+    // 56. OBJETIVOS: Vincular una tarea pendiente a un objetivo del mismo propietario
+    pub fn link_task_to_goal(env: Env, goal_id: u32, task_id: u64, caller: Address) -> Result<(), TaskError> {
+        Self::bump_ttl(&env);
+        let task = Self::load_task_for_update(&env, task_id, &caller, (goal_id, task_id).into_val(&env))?;
+
+        let goal: Goal = env
+            .storage()
+            .instance()
+            .get(&DataKey::Goal(goal_id))
+            .ok_or(TaskError::GoalNotFound)?;
+
+        if goal.owner != task.owner {
+            return Err(TaskError::Unauthorized);
+        }
+        if task.status != TaskStatus::Pending {
+            return Err(TaskError::TaskAlreadyCompleted);
+        }
+        if env.storage().instance().has(&DataKey::TaskGoal(task_id)) {
+            return Err(TaskError::InvalidTaskData);
+        }
+
+        env.storage().instance().set(&DataKey::TaskGoal(task_id), &goal_id);
+        Ok(())
+    }
+
+    // 57. LEER: Objetivos de un propietario con su progreso y estado de la fecha límite
+    pub fn get_goals(env: Env, owner: Address) -> Vec<GoalStatus> {
+        let mut goals = Vec::new(&env);
+        let now = env.ledger().timestamp();
+        let goal_ids: Vec<u32> = env.storage().instance().get(&DataKey::OwnerGoals(owner)).unwrap_or(Vec::new(&env));
+
+        for goal_id in goal_ids.iter() {
+            if let Some(goal) = env.storage().instance().get::<DataKey, Goal>(&DataKey::Goal(goal_id)) {
+                let achieved = goal.progress >= goal.target_count;
+                let past_deadline = now > goal.deadline;
+                goals.push_back(GoalStatus { goal, achieved, past_deadline });
+            }
+        }
+        goals
+    }
+
+
+    // 58. LEER: Informe de la semana que empieza en 'week_start_ts' (7 días UTC)
+    // Se calcula a partir de los cubos diarios mantenidos en cada operación, sin recorrer tareas.
+    pub fn get_weekly_report(env: Env, owner: Address, week_start_ts: u64) -> WeeklyReport {
+        let first_day = week_start_ts / DAY_SECS;
+        let today = env.ledger().timestamp() / DAY_SECS;
+
+        let mut report = WeeklyReport {
+            created: 0,
+            completed: 0,
+            overdue: 0,
+            carried_over: 0,
+        };
+
+        for day in first_day..first_day + 7 {
+            if let Some(stats) = env.storage().instance().get::<DataKey, DayStats>(&DataKey::DayStats(owner.clone(), day)) {
+                report.created += stats.created;
+                report.completed += stats.completed;
+            }
+            // Solo vencen los días ya terminados
+            if day < today {
+                report.overdue += env.storage().instance().get::<DataKey, u32>(&DataKey::DueOpen(owner.clone(), day)).unwrap_or(0);
+            }
+        }
+
+        // Arrastradas: tareas abiertas al cierre del último día con actividad antes de la semana
+        let active_days: Vec<u64> = env.storage().instance().get(&DataKey::ActiveDays(owner.clone())).unwrap_or(Vec::new(&env));
+        let idx = match active_days.binary_search(first_day) {
+            Ok(i) | Err(i) => i,
+        };
+        if idx > 0 {
+            let day = active_days.get(idx - 1).unwrap();
+            if let Some(stats) = env.storage().instance().get::<DataKey, DayStats>(&DataKey::DayStats(owner, day)) {
+                report.carried_over = stats.open_end;
+            }
+        }
+
+        report
+    }
+
+
+    // 59. LEER: Punto de control para indexadores: (secuencia del ledger, eventos emitidos)
+    // Cada evento lleva su número de secuencia; si el indexador ve un salto, le falta un evento.
+    // Solo cuenta eventos: los cambios que no emiten ninguno (session keys, permisos de edición,
+    // pomodoros, objetivos, roles...) no lo mueven.
+    pub fn get_checkpoint(env: Env) -> (u64, u32) {
+        let event_seq: u32 = env.storage().instance().get(&EVENT_SEQ_KEY).unwrap_or(0);
+        (env.ledger().sequence() as u64, event_seq)
+    }
+
+
+    // 60. LEER: Periodo de gracia de purga vigente
+    pub fn get_purge_grace(env: Env) -> u64 {
+        env.storage().instance().get(&PURGE_GRACE_KEY).unwrap_or(DEFAULT_PURGE_GRACE)
+    }
+
+    // 61. ELIMINAR (Hard Delete): Borrar definitivamente una tarea ya eliminada
+    // Solo tras el periodo de gracia desde su borrado, para que una session key comprometida
+    // no pueda destruir datos de forma inmediata e irreversible. Se borran también todas sus
+    // entradas auxiliares salvo los permisos de edición delegados, que no se pueden enumerar
+    // (caducan solos y los IDs no se reutilizan).
+    pub fn purge_task(env: Env, task_id: u64, caller: Address) -> Result<(), TaskError> {
+        Self::bump_ttl(&env);
+        let task = Self::load_task_for_update(&env, task_id, &caller, (task_id,).into_val(&env))?;
+
+        if task.status != TaskStatus::Deleted {
+            return Err(TaskError::InvalidTaskData);
+        }
+
+        // Las tareas borradas antes de registrar 'DeletedAt' usan su última modificación
+        let deleted_at: u64 = env.storage().instance().get(&DataKey::DeletedAt(task_id)).unwrap_or(task.updated_at);
+        if env.ledger().timestamp() < deleted_at.saturating_add(Self::get_purge_grace(env.clone())) {
+            return Err(TaskError::PurgeTooSoon);
+        }
+
+        env.storage().instance().remove(&DataKey::Task(task_id));
+        Self::status_index_remove(&env, &TaskStatus::Deleted, task_id);
+        env.storage().instance().remove(&DataKey::DeletedAt(task_id));
+        env.storage().instance().remove(&DataKey::FrozenTask(task_id));
+        env.storage().instance().remove(&DataKey::ScheduledFor(task_id));
+        env.storage().instance().remove(&DataKey::AgedAt(task_id));
+        env.storage().instance().remove(&DataKey::BlockedReason(task_id));
+        env.storage().instance().remove(&DataKey::ActivePomodoro(task_id));
+        env.storage().instance().remove(&DataKey::Recovery(task_id));
+        env.storage().instance().remove(&DataKey::Hidden(task_id));
+        env.storage().temporary().remove(&DataKey::TaskLock(task_id));
+        if let Some((creator, seq)) = Self::get_task_seq(env.clone(), task_id) {
+            env.storage().instance().remove(&DataKey::OwnerSeq(creator, seq));
+            env.storage().instance().remove(&DataKey::TaskSeq(task_id));
+        }
+        env.storage().instance().remove(&DataKey::Habit(task_id));
+        env.storage().instance().remove(&DataKey::TaskGoal(task_id));
+        env.storage().instance().remove(&DataKey::TaskFocus(task_id));
+        env.storage().instance().remove(&DataKey::UndoSnapshot(task_id));
+        env.storage().instance().remove(&DataKey::DescriptionHash(task_id));
+        env.storage().instance().remove(&DataKey::EncryptedDescription(task_id));
+        env.storage().instance().remove(&DataKey::CompletionCommitment(task_id));
+        env.storage().instance().remove(&DataKey::CompletionProof(task_id));
+        env.storage().instance().remove(&DataKey::TaskOracle(task_id));
+        env.storage().instance().remove(&OrgKey::TaskLang(task_id));
+        env.storage().instance().remove(&OrgKey::TaskColor(task_id));
+        env.storage().instance().remove(&OrgKey::TaskIcon(task_id));
+        Self::remove_location(&env, task_id);
+        Self::remove_context(&env, task_id, &task.owner);
+        Self::remove_effort(&env, task_id, &task.owner);
+        env.storage().instance().remove(&OrgKey::WaitingOn(task_id));
+        Self::remove_review(&env, task_id, &task.owner);
+        Self::remove_dependencies(&env, task_id);
+        env.storage().instance().remove(&OrgKey::Subtasks(task_id));
+        env.storage().instance().remove(&OrgKey::ParentTask(task_id));
+        env.storage().instance().remove(&OrgKey::MergedInto(task_id));
+        env.storage().instance().remove(&OrgKey::MergedFrom(task_id));
+        Self::remove_links(&env, task_id);
+
+        // Quitarla del índice del propietario
+        let mut owner_tasks: Vec<u64> = Self::load_ids(&env, &task.owner);
+        if let Some(i) = owner_tasks.first_index_of(task_id) {
+            owner_tasks.remove(i);
+            env.storage().instance().set(&task.owner, &owner_tasks);
+        }
+
+        Self::emit_task_event(&env, symbol_short!("purged"), task_id);
+        Ok(())
+    }
+
+
+    // 62. DESHACER: Restaurar la versión anterior a la última modificación del propietario
+    // Debe hacerse dentro de UNDO_WINDOW segundos; los contadores derivados (tareas abiertas,
+    // vencimientos, cubos del día, estadísticas globales y objetivos) se reajustan. Los cambios
+    // posteriores de los keepers también se pierden. No se revierten el crédito de referido ya
+    // contado ni la preimagen publicada al concluir con compromiso, que ya es pública. Tras cambiar
+    // entradas auxiliares que la instantánea no guarda (descripción privada o cifrada, bloqueo,
+    // espera, ubicación, contexto, esfuerzo, apariencia o revisión) no hay nada que deshacer.
+    pub fn undo(env: Env, task_id: u64, caller: Address) -> Result<(), TaskError> {
+        Self::bump_ttl(&env);
+        let current = Self::load_task_for_update(&env, task_id, &caller, (task_id,).into_val(&env))?;
+
+        let snapshot: UndoSnapshot = env
+            .storage()
+            .instance()
+            .get(&DataKey::UndoSnapshot(task_id))
+            .ok_or(TaskError::NothingToUndo)?;
+
+        if env.ledger().timestamp() > snapshot.saved_at + UNDO_WINDOW {
+            return Err(TaskError::NothingToUndo);
+        }
+        // Una transferencia no se deshace desde aquí: la versión anterior es de otro propietario
+        if snapshot.task.owner != current.owner {
+            return Err(TaskError::Unauthorized);
+        }
+
+        let mut previous = snapshot.task;
+
+        // Reajustar tareas abiertas, vencimientos, objetivos y borrado según el cambio de estado
+        let was_open = Self::is_open(&current.status);
+        let reopens = Self::is_open(&previous.status);
+        if was_open {
+            Self::track_due(&env, &current, false);
+        }
+        if reopens {
+            Self::track_due(&env, &previous, true);
+        }
+        if was_open != reopens {
+            let (key, stats) = Self::adjust_open_count(&env, &current.owner, reopens);
+            env.storage().instance().set(&key, &stats);
+        }
+        if current.status != previous.status {
+            match current.status {
+                TaskStatus::Completed => Self::count_stat(&env, |stats| stats.completed = stats.completed.saturating_sub(1)),
+                TaskStatus::Deleted => Self::count_stat(&env, |stats| stats.deleted = stats.deleted.saturating_sub(1)),
+                TaskStatus::Cancelled => Self::count_stat(&env, |stats| stats.cancelled = stats.cancelled.saturating_sub(1)),
+                _ => {}
+            }
+        }
+        if current.status == TaskStatus::Completed && previous.status != TaskStatus::Completed {
+            // La conclusión se anotó en el cubo del día en que ocurrió
+            let key = DataKey::DayStats(current.owner.clone(), current.updated_at / DAY_SECS);
+            if let Some(mut stats) = env.storage().instance().get::<DataKey, DayStats>(&key) {
+                stats.completed = stats.completed.saturating_sub(1);
+                env.storage().instance().set(&key, &stats);
+            }
+            if let Some(goal_id) = env.storage().instance().get::<DataKey, u32>(&DataKey::TaskGoal(task_id)) {
+                if let Some(mut goal) = env.storage().instance().get::<DataKey, Goal>(&DataKey::Goal(goal_id)) {
+                    goal.progress = goal.progress.saturating_sub(1);
+                    env.storage().instance().set(&DataKey::Goal(goal_id), &goal);
+                }
+            }
+        }
+        if current.status == TaskStatus::Deleted && previous.status != TaskStatus::Deleted {
+            env.storage().instance().remove(&DataKey::DeletedAt(task_id));
+        }
+
+        Self::save_task(&env, &mut previous);
+        // Deshacer no se puede deshacer: se descarta la instantánea recién creada
+        env.storage().instance().remove(&DataKey::UndoSnapshot(task_id));
+
+        Self::emit_status_event(&env, symbol_short!("undone"), task_id, current.status, previous.status);
+        Ok(())
+    }
+
+
+    // 63. BLOQUEAR: Edición exclusiva de una tarea durante 'ttl_ledgers' ledgers
+    // Mientras dure, cualquier otra dirección que intente modificarla recibe TaskLocked.
+    // El titular puede renovarlo volviendo a llamar.
+    pub fn lock_task(env: Env, task_id: u64, caller: Address, ttl_ledgers: u32) -> Result<(), TaskError> {
+        Self::bump_ttl(&env);
+        Self::load_task_for_edit(&env, task_id, &caller, (task_id, ttl_ledgers).into_val(&env))?;
+
+        if ttl_ledgers == 0 || ttl_ledgers > env.storage().max_ttl() {
+            return Err(TaskError::InvalidTaskData);
+        }
+
+        let lock = TaskLock {
+            holder: caller,
+            expires_at: env.ledger().sequence() + ttl_ledgers,
+        };
+        let key = DataKey::TaskLock(task_id);
+        env.storage().temporary().set(&key, &lock);
+        env.storage().temporary().extend_ttl(&key, ttl_ledgers, ttl_ledgers);
+        Ok(())
+    }
+
+    // 64. BLOQUEAR: Liberar el bloqueo (el titular, o quien puede actuar por el propietario)
+    pub fn unlock_task(env: Env, task_id: u64, caller: Address) -> Result<(), TaskError> {
+        Self::bump_ttl(&env);
+        caller.require_auth_for_args((task_id,).into_val(&env));
+
+        let lock = match Self::get_task_lock(env.clone(), task_id) {
+            Some(lock) => lock,
+            None => return Ok(()),
+        };
+        let task = Self::load_task(&env, task_id).ok_or(TaskError::TaskNotFound)?;
+
+        if lock.holder != caller && !Self::can_act_for(&env, &task.owner, &caller) {
+            return Err(TaskError::Unauthorized);
+        }
+
+        env.storage().temporary().remove(&DataKey::TaskLock(task_id));
+        Ok(())
+    }
+
+    // 65. LEER: Bloqueo vigente de una tarea (None si no hay o ya expiró)
+    pub fn get_task_lock(env: Env, task_id: u64) -> Option<TaskLock> {
+        let lock: TaskLock = env.storage().temporary().get(&DataKey::TaskLock(task_id))?;
+        if env.ledger().sequence() > lock.expires_at {
+            return None;
+        }
+        Some(lock)
+    }
+
+
+    // 66. LEER: Próximo nonce de un delegado (session key o editor), a incluir como último
+    // argumento firmado en su siguiente operación delegada
+    pub fn get_nonce(env: Env, addr: Address) -> u64 {
+        env.storage().instance().get(&DataKey::Nonce(addr)).unwrap_or(0)
+    }
+
+
+    // 67. REFERIDOS: Un usuario nuevo (sin tareas) registra quién lo refirió
+    pub fn register_referral(env: Env, new_user: Address, referrer: Address) -> Result<(), TaskError> {
+        new_user.require_auth();
+        Self::ensure_account_active(&env, &new_user)?;
+
+        // Nada de auto-referidos ni de referidos en círculo
+        if new_user == referrer {
+            return Err(TaskError::InvalidReferral);
+        }
+        if let Some(upstream) = env.storage().instance().get::<DataKey, Referral>(&DataKey::Referral(referrer.clone())) {
+            if upstream.referrer == new_user {
+                return Err(TaskError::InvalidReferral);
+            }
+        }
+
+        // Solo una vez, y antes de haber creado tareas
+        let has_tasks = env.storage().instance().get::<Address, Vec<u64>>(&new_user).is_some_and(|ids| !ids.is_empty());
+        if has_tasks || env.storage().instance().has(&DataKey::Referral(new_user.clone())) {
+            return Err(TaskError::InvalidReferral);
+        }
+
+        let referral = Referral {
+            referrer,
+            completed: 0,
+            rewarded: false,
+        };
+        env.storage().instance().set(&DataKey::Referral(new_user), &referral);
+        Ok(())
+    }
+
+    // 68. LEER: Referido registrado por un usuario
+    pub fn get_referral(env: Env, user: Address) -> Option<Referral> {
+        env.storage().instance().get(&DataKey::Referral(user))
+    }
+
+    // 69. LEER: Puntos de referido acumulados
+    pub fn get_referral_points(env: Env, referrer: Address) -> u32 {
+        env.storage().instance().get(&DataKey::ReferralPoints(referrer)).unwrap_or(0)
+    }
+
+
+    // 70. ADMIN (timelock): Proponer una acción sensible (configuración, upgrade); se podrá
+    // ejecutar pasada la espera del timelock, lo que da tiempo a los usuarios para reaccionar.
+    pub fn schedule_action(env: Env, action: AdminAction) -> Result<u32, TaskError> {
+        Self::require_admin(&env)?;
+        if let AdminAction::SetTtlPolicy(threshold, extend_to) = action {
+            if threshold >= extend_to || extend_to > env.storage().max_ttl() {
+                return Err(TaskError::InvalidTaskData);
+            }
+        }
+        Self::queue_action(&env, action)
+    }
+
+    // 71. ADMIN (timelock): Ejecutar una acción programada cuyo plazo ya venció
+    // Cualquiera puede llamarla: la decisión ya la tomó el admin al programarla.
+    pub fn execute_action(env: Env, action_id: u32) -> Result<(), TaskError> {
+        Self::bump_ttl(&env);
+        let key = DataKey::PendingAction(action_id);
+        let pending: PendingAction = env.storage().instance().get(&key).ok_or(TaskError::ActionNotFound)?;
+
+        if env.ledger().timestamp() < pending.eta {
+            return Err(TaskError::TimelockNotElapsed);
+        }
+
+        match pending.action {
+            AdminAction::SetPurgeGrace(grace_secs) => env.storage().instance().set(&PURGE_GRACE_KEY, &grace_secs),
+            AdminAction::SetAdmin(new_admin) => env.storage().instance().set(&ADMIN_KEY, &new_admin),
+            AdminAction::SetActionDelay(delay) => env.storage().instance().set(&ACTION_DELAY_KEY, &delay),
+            AdminAction::Upgrade(wasm_hash) => env.deployer().update_current_contract_wasm(wasm_hash),
+            AdminAction::ReassignTask(task_id, new_owner, reason) => Self::reassign_task(&env, task_id, new_owner, reason)?,
+            AdminAction::SetStatsSink(Some(sink)) => env.storage().instance().set(&STATS_SINK_KEY, &sink),
+            AdminAction::SetStatsSink(None) => env.storage().instance().remove(&STATS_SINK_KEY),
+            AdminAction::SetPublicEnumeration(enabled) => env.storage().instance().set(&PUBLIC_ENUM_KEY, &enabled),
+            AdminAction::SetTtlPolicy(threshold, extend_to) => env.storage().instance().set(&TTL_POLICY_KEY, &(threshold, extend_to)),
+            AdminAction::SetTransferClosed(allowed) => env.storage().instance().set(&TRANSFER_CLOSED_KEY, &allowed),
+        }
+
+        env.storage().instance().remove(&key);
+        Self::emit_admin_event(&env, symbol_short!("executed"), action_id);
+        Ok(())
+    }
+
+    // 72. LEER: Acción programada pendiente de ejecutar
+    pub fn get_pending_action(env: Env, action_id: u32) -> Option<PendingAction> {
+        env.storage().instance().get(&DataKey::PendingAction(action_id))
+    }
+
+    // 73. ADMIN (timelock): Descartar una acción programada antes de que se ejecute
+    pub fn cancel_action(env: Env, action_id: u32) -> Result<(), TaskError> {
+        Self::require_admin(&env)?;
+
+        let key = DataKey::PendingAction(action_id);
+        if !env.storage().instance().has(&key) {
+            return Err(TaskError::ActionNotFound);
+        }
+        env.storage().instance().remove(&key);
+
+        Self::emit_admin_event(&env, symbol_short!("cancelled"), action_id);
+        Ok(())
+    }
+
+    // 74. LEER: Espera obligatoria vigente del timelock (segundos)
+    pub fn get_action_delay(env: Env) -> u64 {
+        env.storage().instance().get(&ACTION_DELAY_KEY).unwrap_or(DEFAULT_ACTION_DELAY)
+    }
+
+    // 75. ADMIN (timelock): Programar la reasignación de emergencia de una tarea
+    // Para recuperar tareas enviadas a direcciones quemadas o equivocadas. El motivo es obligatorio
+    // y queda registrado (ver 'get_recovery'). Retorna el ID de la acción programada.
+    pub fn admin_reassign(env: Env, task_id: u64, new_owner: Address, reason: String) -> Result<u32, TaskError> {
+        Self::bump_ttl(&env);
+        let task = Self::load_task(&env, task_id).ok_or(TaskError::TaskNotFound)?;
+        if reason.is_empty() || task.owner == new_owner {
+            return Err(TaskError::InvalidTaskData);
+        }
+
+        Self::schedule_action(env, AdminAction::ReassignTask(task_id, new_owner, reason))
+    }
+
+    // 76. LEER: Última reasignación de emergencia de una tarea
+    pub fn get_recovery(env: Env, task_id: u64) -> Option<RecoveryRecord> {
+        env.storage().instance().get(&DataKey::Recovery(task_id))
+    }
+
+    // 77. ROLES: Asignar un rol a una dirección (solo el admin)
+    pub fn grant_role(env: Env, role: Role, addr: Address) -> Result<(), TaskError> {
+        Self::require_admin(&env)?;
+        env.storage().instance().set(&DataKey::Role(role, addr), &true);
+        Ok(())
+    }
+
+    // 78. ROLES: Retirar un rol (solo el admin)
+    pub fn revoke_role(env: Env, role: Role, addr: Address) -> Result<(), TaskError> {
+        Self::require_admin(&env)?;
+        env.storage().instance().remove(&DataKey::Role(role, addr));
+        Ok(())
+    }
+
+    // 79. LEER: Indica si una dirección tiene un rol asignado
+    pub fn has_role(env: Env, role: Role, addr: Address) -> bool {
+        env.storage().instance().has(&DataKey::Role(role, addr))
+    }
+
+    // 80. ROLES: Un Upgrader (o el admin) programa una actualización del WASM con el timelock
+    pub fn schedule_upgrade(env: Env, caller: Address, wasm_hash: BytesN<32>) -> Result<u32, TaskError> {
+        caller.require_auth();
+        if !Self::is_admin_or(&env, Role::Upgrader, &caller) {
+            return Err(TaskError::Unauthorized);
+        }
+        Self::queue_action(&env, AdminAction::Upgrade(wasm_hash))
+    }
+
+
+    // 81. MODERAR: Ocultar una tarea del listado público (no la borra; su propietario la sigue viendo)
+    pub fn moderate_hide(env: Env, task_id: u64, moderator: Address, reason: String) -> Result<(), TaskError> {
+        Self::bump_ttl(&env);
+        moderator.require_auth();
+        if !Self::is_admin_or(&env, Role::Moderator, &moderator) {
+            return Err(TaskError::Unauthorized);
+        }
+        if Self::load_task(&env, task_id).is_none() {
+            return Err(TaskError::TaskNotFound);
+        }
+
+        env.storage().instance().set(&DataKey::Hidden(task_id), &reason);
+        Self::emit_task_event(&env, symbol_short!("hidden"), task_id);
+        Ok(())
+    }
+
+    // 82. MODERAR: Volver a mostrar una tarea ocultada
+    pub fn moderate_unhide(env: Env, task_id: u64, moderator: Address) -> Result<(), TaskError> {
+        moderator.require_auth();
+        if !Self::is_admin_or(&env, Role::Moderator, &moderator) {
+            return Err(TaskError::Unauthorized);
+        }
+
+        env.storage().instance().remove(&DataKey::Hidden(task_id));
+        Self::emit_task_event(&env, symbol_short!("unhidden"), task_id);
+        Ok(())
+    }
+
+    // 83. LEER: Indica si una tarea está oculta por moderación
+    pub fn is_hidden(env: Env, task_id: u64) -> bool {
+        env.storage().instance().has(&DataKey::Hidden(task_id))
+    }
+
+
+    // 84. PRIVACIDAD: Crear una tarea guardando solo el sha256 de su descripción
+    // El texto se queda fuera de la cadena; la descripción queda vacía hasta 'reveal_description'.
+    pub fn add_task_private(env: Env, desc_hash: BytesN<32>, owner: Address) -> Result<u64, TaskError> {
+        Self::bump_ttl(&env);
+        owner.require_auth();
+        Self::ensure_account_active(&env, &owner)?;
+
+        let task_id = Self::insert_task(&env, String::from_str(&env, ""), owner, TaskStatus::Pending)?;
+        env.storage().instance().set(&DataKey::DescriptionHash(task_id), &desc_hash);
+        Ok(task_id)
+    }
+
+    // 85. PRIVACIDAD: Revelar la descripción de una tarea privada, comprobando su hash
+    pub fn reveal_description(env: Env, task_id: u64, caller: Address, description: String) -> Result<(), TaskError> {
+        Self::bump_ttl(&env);
+        let mut task = Self::load_task_for_update(&env, task_id, &caller, (task_id, description.clone()).into_val(&env))?;
+
+        let expected = Self::get_description_hash(env.clone(), task_id).ok_or(TaskError::InvalidTaskData)?;
+        let preimage = Self::string_to_bytes(&env, &description)?;
+        let actual: BytesN<32> = env.crypto().sha256(&preimage).into();
+        if actual != expected {
+            return Err(TaskError::HashMismatch);
+        }
+
+        task.description = description;
+        Self::save_task(&env, &mut task);
+        env.storage().instance().remove(&DataKey::DescriptionHash(task_id));
+        Self::discard_undo(&env, task_id);
+
+        Self::emit_task_event(&env, symbol_short!("revealed"), task_id);
+        Ok(())
+    }
+
+    // 86. LEER: Hash comprometido de una tarea privada (None si es pública o ya se reveló)
+    pub fn get_description_hash(env: Env, task_id: u64) -> Option<BytesN<32>> {
+        env.storage().instance().get(&DataKey::DescriptionHash(task_id))
+    }
+
+
+    // 87. PRIVACIDAD: Registrar la clave pública con la que los clientes cifran las descripciones
+    pub fn set_encryption_pubkey(env: Env, owner: Address, pubkey: BytesN<32>) -> Result<(), TaskError> {
+        owner.require_auth();
+        Self::ensure_account_active(&env, &owner)?;
+        env.storage().instance().set(&DataKey::EncryptionPubkey(owner), &pubkey);
+        Ok(())
+    }
+
+    // 88. LEER: Clave pública de cifrado de un propietario
+    pub fn get_encryption_pubkey(env: Env, owner: Address) -> Option<BytesN<32>> {
+        env.storage().instance().get(&DataKey::EncryptionPubkey(owner))
+    }
+
+    // 89. PRIVACIDAD: Crear una tarea con la descripción cifrada (la descripción en claro queda vacía)
+    // Las claves se comparten fuera de la cadena; 'get_task_detail' devuelve el texto cifrado.
+    pub fn add_task_encrypted(env: Env, ciphertext: Bytes, owner: Address) -> Result<u64, TaskError> {
+        Self::bump_ttl(&env);
+        owner.require_auth();
+        Self::ensure_account_active(&env, &owner)?;
+        Self::validate_ciphertext(&ciphertext)?;
+
+        let task_id = Self::insert_task(&env, String::from_str(&env, ""), owner, TaskStatus::Pending)?;
+        env.storage().instance().set(&DataKey::EncryptedDescription(task_id), &ciphertext);
+        Ok(task_id)
+    }
+
+    // 90. ACTUALIZAR: Reemplazar la descripción cifrada de una tarea pendiente
+    pub fn update_encrypted_description(env: Env, task_id: u64, caller: Address, ciphertext: Bytes) -> Result<(), TaskError> {
+        Self::bump_ttl(&env);
+        let mut task = Self::load_task_for_edit(&env, task_id, &caller, (task_id, ciphertext.clone()).into_val(&env))?;
+        Self::validate_ciphertext(&ciphertext)?;
+
+        if task.status != TaskStatus::Pending {
+            return Err(TaskError::TaskAlreadyCompleted);
+        }
+
+        env.storage().instance().set(&DataKey::EncryptedDescription(task_id), &ciphertext);
+        Self::save_task(&env, &mut task);
+        Self::discard_undo(&env, task_id);
+        Self::emit_task_event(&env, symbol_short!("updated"), task_id);
+        Ok(())
+    }
+
+
+    // 91. COMPROMISOS: Exigir una preimagen para concluir la tarea (pago contra revelación:
+    // p.ej. el hash de un entregable o de una clave). Solo mientras la tarea está pendiente.
+    pub fn set_completion_commitment(env: Env, task_id: u64, caller: Address, commitment: BytesN<32>) -> Result<(), TaskError> {
+        Self::bump_ttl(&env);
+        let task = Self::load_task_for_update(&env, task_id, &caller, (task_id, commitment.clone()).into_val(&env))?;
+
+        if task.status != TaskStatus::Pending {
+            return Err(TaskError::TaskAlreadyCompleted);
+        }
+
+        env.storage().instance().set(&DataKey::CompletionCommitment(task_id), &commitment);
+        Ok(())
+    }
+
+    // 92. COMPROMISOS: Concluir una tarea revelando la preimagen de su compromiso
+    // La preimagen queda guardada (ver 'get_completion_proof') para quien esperaba el secreto.
+    pub fn complete_with_proof(env: Env, task_id: u64, caller: Address, preimage: Bytes) -> Result<(), TaskError> {
+        Self::bump_ttl(&env);
+        let task = Self::load_task_for_update(&env, task_id, &caller, (task_id, preimage.clone()).into_val(&env))?;
+
+        let commitment: BytesN<32> = env
+            .storage()
+            .instance()
+            .get(&DataKey::CompletionCommitment(task_id))
+            .ok_or(TaskError::InvalidTaskData)?;
+        let digest: BytesN<32> = env.crypto().sha256(&preimage).into();
+        if digest != commitment {
+            return Err(TaskError::HashMismatch);
+        }
+
+        Self::complete_task(&env, task)?;
+        env.storage().instance().set(&DataKey::CompletionProof(task_id), &preimage);
+        Ok(())
+    }
+
+    // 93. LEER: Preimagen revelada al concluir una tarea con compromiso
+    pub fn get_completion_proof(env: Env, task_id: u64) -> Option<Bytes> {
+        env.storage().instance().get(&DataKey::CompletionProof(task_id))
+    }
+
+
+    // 94. IMPORTAR: Copiar tareas de un despliegue anterior (esquema v1) a este contrato
+    // Lee cada ID con 'get_task_by_id' del contrato de origen y la recrea aquí con un ID nuevo,
+    // conservando descripción, propietario y estado. Cada propietario debe firmar. Las tareas
+    // eliminadas se omiten. Los IDs de origen son u32, como en el esquema v1.
+    // Retorna los IDs nuevos, en el mismo orden.
+    pub fn import_tasks(env: Env, source_contract: Address, ids: Vec<u32>) -> Result<Vec<u64>, TaskError> {
+        Self::bump_ttl(&env);
+        if source_contract == env.current_contract_address() {
+            return Err(TaskError::InvalidTaskData);
+        }
+
+        let mut authorized: Vec<Address> = Vec::new(&env);
+        let mut new_ids: Vec<u64> = Vec::new(&env);
+        let func = Symbol::new(&env, "get_task_by_id");
+
+        for id in ids.iter() {
+            let old: Option<TaskV1> = env.invoke_contract(&source_contract, &func, (id,).into_val(&env));
+            let old = old.ok_or(TaskError::TaskNotFound)?;
+
+            if old.status == TaskStatus::Deleted {
+                continue;
+            }
+
+            // Una sola firma por propietario aunque importe varias tareas
+            if !authorized.contains(&old.owner) {
+                old.owner.require_auth();
+                authorized.push_back(old.owner.clone());
+            }
+
+            let status = old.status.clone();
+            let new_id = Self::create_task(&env, old.description, old.owner.clone(), status.clone())?;
+            if !Self::is_open(&status) {
+                // Llega ya cerrada: no cuenta como abierta en las estadísticas
+                Self::record_day_activity(&env, &old.owner, false, false);
+            }
+            new_ids.push_back(new_id);
+        }
+        Ok(new_ids)
+    }
+
+
+    // 95. EXPORTAR: Tareas del rango de IDs [from_id, to_id] en el formato estable TaskExport
+    // Como máximo MAX_EXPORT_RANGE IDs por llamada; los IDs sin registro se omiten.
+    pub fn export_range(env: Env, from_id: u64, to_id: u64) -> Result<Vec<TaskExport>, TaskError> {
+        Self::bump_ttl(&env);
+        if from_id == 0 || to_id < from_id || to_id - from_id >= MAX_EXPORT_RANGE {
+            return Err(TaskError::InvalidTaskData);
+        }
+
+        let mut exported = Vec::new(&env);
+        let last_id = Self::get_next_task_id(&env);
+
+        for id in from_id..=to_id.min(last_id.saturating_sub(1)) {
+            if let Some(task) = Self::load_task(&env, id) {
+                exported.push_back(TaskExport {
+                    id: task.id,
+                    description: task.description,
+                    owner: task.owner,
+                    status: match task.status {
+                        TaskStatus::Pending => 0,
+                        TaskStatus::Completed => 1,
+                        TaskStatus::Deleted => 2,
+                        TaskStatus::Scheduled => 3,
+                        TaskStatus::Blocked => 4,
+                        TaskStatus::Cancelled => 5,
+                        TaskStatus::WaitingOn => 6,
+                        TaskStatus::Backlog => 7,
+                    },
+                    timestamp: task.timestamp,
+                    priority: match task.priority {
+                        TaskPriority::Low => 0,
+                        TaskPriority::Medium => 1,
+                        TaskPriority::High => 2,
+                        TaskPriority::Critical => 3,
+                    },
+                    due_date: task.due_date.unwrap_or(0),
+                    updated_at: task.updated_at,
+                });
+            }
+        }
+        Ok(exported)
+    }
+
+
+    // 96. ORÁCULOS: Registrar (o retirar con 'enabled' = false) un contrato oráculo (solo el admin)
+    pub fn set_oracle(env: Env, oracle: Address, enabled: bool) -> Result<(), TaskError> {
+        Self::require_admin(&env)?;
+        if enabled {
+            env.storage().instance().set(&DataKey::Oracle(oracle), &true);
+        } else {
+            env.storage().instance().remove(&DataKey::Oracle(oracle));
+        }
+        Ok(())
+    }
+
+    // 97. LEER: Indica si un oráculo está registrado
+    pub fn is_oracle(env: Env, oracle: Address) -> bool {
+        env.storage().instance().has(&DataKey::Oracle(oracle))
+    }
+
+    // 98. ORÁCULOS: Exigir que un oráculo registrado confirme 'claim' antes de concluir la tarea
+    // 'task_completed' llamará a 'verify(task_id, claim)' y solo procederá si retorna true.
+    pub fn set_task_oracle(env: Env, task_id: u64, caller: Address, oracle: Address, claim: Bytes) -> Result<(), TaskError> {
+        Self::bump_ttl(&env);
+        let task = Self::load_task_for_update(&env, task_id, &caller, (task_id, oracle.clone(), claim.clone()).into_val(&env))?;
+
+        if task.status != TaskStatus::Pending {
+            return Err(TaskError::TaskAlreadyCompleted);
+        }
+        if !Self::is_oracle(env.clone(), oracle.clone()) {
+            return Err(TaskError::InvalidTaskData);
+        }
+
+        env.storage().instance().set(&DataKey::TaskOracle(task_id), &OracleCheck { oracle, claim });
+        Ok(())
+    }
+
+    // 99. LEER: Verificación de oráculo exigida por una tarea
+    pub fn get_task_oracle(env: Env, task_id: u64) -> Option<OracleCheck> {
+        env.storage().instance().get(&DataKey::TaskOracle(task_id))
+    }
+
+
+    // 100. ESTADÍSTICAS: Contrato de estadísticas configurado (se cambia con el timelock)
+    pub fn get_stats_sink(env: Env) -> Option<Address> {
+        env.storage().instance().get(&STATS_SINK_KEY)
+    }
+
+    // 101. LEER: Contadores acumulados aún no enviados
+    pub fn get_pending_stats(env: Env) -> StatsCounters {
+        env.storage().instance().get(&STATS_KEY).unwrap_or_default()
+    }
+
+    // 102. KEEPER: Enviar los contadores acumulados al contrato de estadísticas y reiniciarlos
+    // Cualquiera puede llamarla. Retorna false si no hay contrato configurado o nada que enviar.
+    pub fn flush_stats(env: Env) -> bool {
+        let sink = match Self::get_stats_sink(env.clone()) {
+            Some(sink) => sink,
+            None => return false,
+        };
+        let stats = Self::get_pending_stats(env.clone());
+        if stats == StatsCounters::default() {
+            return false;
+        }
+
+        StatsSinkClient::new(&env, &sink).record_stats(&env.current_contract_address(), &stats);
+        env.storage().instance().remove(&STATS_KEY);
+        true
+    }
+
+
+    /// Función helper: suma a los contadores pendientes de enviar al contrato de estadísticas
+    fn count_stat(env: &Env, update: impl FnOnce(&mut StatsCounters)) {
+        let mut stats: StatsCounters = env.storage().instance().get(&STATS_KEY).unwrap_or_default();
+        update(&mut stats);
+        env.storage().instance().set(&STATS_KEY, &stats);
+    }
+
+    // 103. LEER: Tarea por su identificador por propietario (owner, seq)
+    // 'seq' lo asigna la creación: es la n-ésima tarea creada por 'owner' (desde 1) y no cambia
+    // aunque la tarea se transfiera. Las tareas anteriores a esta numeración solo tienen ID global.
+    pub fn get_task(env: Env, owner: Address, seq: u32) -> Option<Task> {
+        Self::bump_ttl(&env);
+        let task_id: u64 = Self::load_id(&env, &DataKey::OwnerSeq(owner, seq))?;
+        Self::load_task(&env, task_id)
+    }
+
+    // 104. LEER: Identificador por propietario (owner, seq) de un ID global
+    pub fn get_task_seq(env: Env, task_id: u64) -> Option<(Address, u32)> {
+        env.storage().instance().get(&DataKey::TaskSeq(task_id))
+    }
+
+
+    // 105. LEER: Listado global paginado: revisa como mucho 'limit' IDs (<= MAX_PAGE_SIZE) desde
+    // 'cursor' (o desde el 1), con los mismos filtros y permisos que 'get_all'
+    pub fn get_all_page(env: Env, cursor: u64, limit: u32) -> Result<TaskPage, TaskError> {
+        Self::bump_ttl(&env);
+        if limit == 0 || limit > MAX_PAGE_SIZE {
+            return Err(TaskError::InvalidTaskData);
+        }
+        if !Self::is_public_enumeration(env.clone()) {
+            Self::require_admin(&env)?;
+        }
+
+        let last_id = Self::get_next_task_id(&env);
+        let start = cursor.max(1);
+        let end = start.saturating_add(u64::from(limit)).min(last_id);
+
+        let mut tasks = Vec::new(&env);
+        for id in start..end {
+            if let Some(task) = Self::load_listable(&env, id) {
+                tasks.push_back(task);
+            }
+        }
+
+        let next_cursor = if end < last_id { Some(end) } else { None };
+        Ok(TaskPage { tasks, next_cursor })
+    }
+
+    // 106. LEER: Indica si cualquiera puede listar todas las tareas (se cambia con el timelock)
+    pub fn is_public_enumeration(env: Env) -> bool {
+        env.storage().instance().get(&PUBLIC_ENUM_KEY).unwrap_or(true)
+    }
+
+
+    // 107. LEER: Listado global paginado de un estado, usando su índice: cuesta O(limit) sin
+    // importar cuántas tareas de otros estados existan. 'cursor' es el primer ID a incluir.
+    // Las tareas programadas cuya hora ya llegó siguen en Scheduled hasta 'activate_scheduled'.
+    pub fn get_all_paged(env: Env, status: TaskStatus, cursor: u64, limit: u32) -> Result<TaskPage, TaskError> {
+        Self::bump_ttl(&env);
+        if limit == 0 || limit > MAX_PAGE_SIZE {
+            return Err(TaskError::InvalidTaskData);
+        }
+        if !Self::is_public_enumeration(env.clone()) {
+            Self::require_admin(&env)?;
+        }
+
+        let buckets: Vec<u32> = env.storage().instance().get(&DataKey::StatusBuckets(status.clone())).unwrap_or(Vec::new(&env));
+        let first = match buckets.binary_search(Self::status_bucket(cursor)) {
+            Ok(i) | Err(i) => i,
+        };
+
+        let mut tasks = Vec::new(&env);
+        for bucket in buckets.slice(first..).iter() {
+            let ids: Vec<u64> = Self::load_ids(&env, &DataKey::StatusBucket(status.clone(), bucket));
+            for id in ids.iter() {
+                if id < cursor {
+                    continue;
+                }
+                if tasks.len() == limit {
+                    return Ok(TaskPage { tasks, next_cursor: Some(id) });
+                }
+                if Self::is_hidden(env.clone(), id) {
+                    continue;
+                }
+                if let Some(task) = Self::load_task(&env, id) {
+                    tasks.push_back(task);
+                }
+            }
+        }
+        Ok(TaskPage { tasks, next_cursor: None })
+    }
+
+    // 108. MANTENIMIENTO: Indexar por estado las tareas [from_id, to_id] creadas antes del índice
+    // No requiere firma. Retorna el número de tareas añadidas.
+    pub fn reindex_status(env: Env, from_id: u64, to_id: u64) -> Result<u32, TaskError> {
+        Self::bump_ttl(&env);
+        if from_id == 0 || to_id < from_id {
+            return Err(TaskError::InvalidTaskData);
+        }
+
+        let mut added: u32 = 0;
+        for id in from_id..=to_id.min(Self::get_next_task_id(&env).saturating_sub(1)) {
+            if let Some(task) = Self::load_stored_task(&env, id) {
+                if Self::status_index_add(&env, &task.status, id) {
+                    added += 1;
+                }
+            }
+        }
+        Ok(added)
+    }
+
+    // 109. LEER: Política vigente de renovación automática del TTL: (umbral, extensión) en ledgers
+    pub fn get_ttl_policy(env: Env) -> (u32, u32) {
+        env.storage().instance().get(&TTL_POLICY_KEY).unwrap_or((DEFAULT_TTL_THRESHOLD, DEFAULT_TTL_EXTEND_TO))
+    }
+
+    // 110. ARCHIVO: Sacar del almacenamiento una tarea concluida, dejando solo su hash y estado
+    // La tarea completa se emite en el evento 'archived' para poder restaurarla después.
+    pub fn archive_to_cold(env: Env, task_id: u64, caller: Address) -> Result<BytesN<32>, TaskError> {
+        Self::bump_ttl(&env);
+        let task = Self::load_task_for_update(&env, task_id, &caller, (task_id,).into_val(&env))?;
+
+        // El backlog no cuenta como abierta, pero tampoco está cerrada
+        if Self::is_open(&task.status) || task.status == TaskStatus::Backlog {
+            return Err(TaskError::InvalidTaskData);
+        }
+
+        let hash: BytesN<32> = env.crypto().sha256(&task.clone().to_xdr(&env)).into();
+        let tombstone = ColdTombstone {
+            hash: hash.clone(),
+            status: task.status.clone(),
+            archived_at: env.ledger().timestamp(),
+        };
+
+        env.storage().instance().remove(&DataKey::Task(task_id));
+        env.storage().instance().remove(&DataKey::UndoSnapshot(task_id));
+        Self::status_index_remove(&env, &task.status, task_id);
+        env.storage().instance().set(&DataKey::Cold(task_id), &tombstone);
+
+        let event_seq = Self::next_event_seq(&env);
+        env.events().publish((symbol_short!("task"), symbol_short!("archived")), (task, event_seq));
+        Ok(hash)
+    }
+
+    // 111. ARCHIVO: Restaurar una tarea archivada a partir del registro emitido al archivarla
+    // El registro debe coincidir exactamente con el hash guardado; firma su propietario.
+    pub fn restore_from_cold(env: Env, task: Task) -> Result<(), TaskError> {
+        if Self::get_storage_version(env.clone()) < STORAGE_VERSION {
+            Self::migrate_one(&env, task.id);
+        }
+        let key = DataKey::Cold(task.id);
+        let tombstone: ColdTombstone = env.storage().instance().get(&key).ok_or(TaskError::TaskNotFound)?;
+
+        // Las tareas archivadas antes del esquema v3 se verifican con el hash de su registro v2
+        let hash: BytesN<32> = env.crypto().sha256(&task.clone().to_xdr(&env)).into();
+        if hash != tombstone.hash && Self::legacy_hash(&env, &task) != Some(tombstone.hash.clone()) {
+            return Err(TaskError::HashMismatch);
+        }
+        task.owner.require_auth();
+
+        // Se guarda tal cual (sin tocar 'updated_at') para que vuelva a coincidir con el hash
+        Self::bump_ttl(&env);
+        env.storage().instance().set(&DataKey::Task(task.id), &task);
+        Self::status_index_add(&env, &task.status, task.id);
+        env.storage().instance().remove(&key);
+
+        Self::emit_task_event(&env, symbol_short!("restored"), task.id);
+        Ok(())
+    }
+
+    // 112. LEER: Resto de una tarea archivada en frío (None si no está archivada)
+    pub fn get_cold_tombstone(env: Env, task_id: u64) -> Option<ColdTombstone> {
+        match env.storage().instance().get(&DataKey::Cold(task_id)) {
+            Some(tombstone) => Some(tombstone),
+            None => {
+                let legacy_id = u32::try_from(task_id).ok()?;
+                env.storage().instance().get(&LegacyKey::Cold(legacy_id))
+            }
+        }
+    }
+
+    // 113. MANTENIMIENTO: Ampliar el TTL de las tareas indicadas hasta 'extend_to' ledgers
+    // No requiere firma: cualquiera puede mantener vivo un tablero público. Como todas las tareas
+    // comparten el almacenamiento de instancia, se amplía una vez si alguna de ellas existe.
+    // Retorna cuántas de las tareas indicadas existen.
+    pub fn extend_ttls(env: Env, ids: Vec<u64>, extend_to: u32) -> Result<u32, TaskError> {
+        if ids.is_empty() || ids.len() > MAX_PAGE_SIZE || extend_to == 0 || extend_to > env.storage().max_ttl() {
+            return Err(TaskError::InvalidTaskData);
+        }
+
+        let mut found: u32 = 0;
+        for id in ids.iter() {
+            if env.storage().instance().has(&DataKey::Task(id)) {
+                found += 1;
+            }
+        }
+        if found == 0 {
+            return Err(TaskError::TaskNotFound);
+        }
+
+        env.storage().instance().extend_ttl(extend_to, extend_to);
+        Ok(found)
+    }
+
+    // 114. LEER: Estimación de lo que ocupa en almacenamiento un propietario
+    pub fn estimate_footprint(env: Env, owner: Address) -> FootprintReport {
+        Self::bump_ttl(&env);
+        let mut report = FootprintReport {
+            task_entries: 0,
+            side_entries: 0,
+            owner_entries: 0,
+            description_bytes: 0,
+            index_buckets: 0,
+        };
+
+        let task_ids: Vec<u64> = Self::load_ids(&env, &owner);
+        let mut buckets: Vec<(TaskStatus, u32)> = Vec::new(&env);
+        for task_id in task_ids.iter() {
+            if let Some(task) = Self::load_stored_task(&env, task_id) {
+                report.task_entries += 1;
+                report.description_bytes += task.description.len();
+
+                let bucket = (task.status, Self::status_bucket(task_id));
+                if !buckets.contains(&bucket) {
+                    buckets.push_back(bucket);
+                }
+            }
+            if let Some(ciphertext) = env.storage().instance().get::<DataKey, Bytes>(&DataKey::EncryptedDescription(task_id)) {
+                report.description_bytes += ciphertext.len();
+            }
+            report.side_entries += Self::count_present(&env, Self::task_side_keys(&env, task_id));
+            report.side_entries += Self::count_present(&env, Self::task_org_keys(&env, task_id));
+
+            // Su número de secuencia, si sigue registrado (la purga lo borra)
+            if let Some((creator, seq)) = Self::get_task_seq(env.clone(), task_id) {
+                if env.storage().instance().has(&DataKey::OwnerSeq(creator, seq)) {
+                    report.owner_entries += 1;
+                }
+            }
+        }
+        report.index_buckets = buckets.len();
+
+        let owner_keys = soroban_sdk::vec![
+            &env,
+            DataKey::SessionKey(owner.clone()),
+            DataKey::FrozenAccount(owner.clone()),
+            DataKey::PriorityAging(owner.clone()),
+            DataKey::OwnerFocus(owner.clone()),
+            DataKey::UtcOffset(owner.clone()),
+            DataKey::OwnerGoals(owner.clone()),
+            DataKey::ActiveDays(owner.clone()),
+            DataKey::OpenCount(owner.clone()),
+            DataKey::Nonce(owner.clone()),
+            DataKey::Referral(owner.clone()),
+            DataKey::ReferralPoints(owner.clone()),
+            DataKey::EncryptionPubkey(owner.clone()),
+            DataKey::OwnerNextSeq(owner.clone()),
+        ];
+        report.owner_entries += Self::count_present(&env, owner_keys);
+        if env.storage().instance().has(&owner) {
+            report.owner_entries += 1;
+        }
+
+        // Un DayStats por día activo
+        let active_days: Vec<u64> = env.storage().instance().get(&DataKey::ActiveDays(owner.clone())).unwrap_or(Vec::new(&env));
+        report.owner_entries += active_days.len();
+
+        report
+    }
+
+    // 115. LEER: Tarea por ID con error explícito (alternativa a 'get_task_by_id')
+    // Distingue una tarea inexistente (TaskNotFound) de una eliminada (TaskIsDeleted).
+    pub fn get_task_checked(env: Env, task_id: u64) -> Result<Task, TaskError> {
+        Self::bump_ttl(&env);
+        let task = Self::load_task(&env, task_id).ok_or(TaskError::TaskNotFound)?;
+        if task.status == TaskStatus::Deleted {
+            return Err(TaskError::TaskIsDeleted);
+        }
+        Ok(task)
+    }
+
+    // 116. LEER (papelera): Registro de una tarea eliminada que aún no se ha purgado
+    pub fn get_deleted_task(env: Env, task_id: u64) -> Result<Task, TaskError> {
+        Self::bump_ttl(&env);
+        match Self::load_task(&env, task_id) {
+            Some(task) if task.status == TaskStatus::Deleted => Ok(task),
+            _ => Err(TaskError::TaskNotFound),
+        }
+    }
+
+    // 117. LEER: Indica si 'transfer_ownership' admite tareas completadas o eliminadas
+    // (se cambia con el timelock)
+    pub fn is_closed_transferable(env: Env) -> bool {
+        env.storage().instance().get(&TRANSFER_CLOSED_KEY).unwrap_or(false)
+    }
+
+    // 118. CREAR: Añadir una tarea indicando el idioma de su descripción
+    pub fn add_task_lang(env: Env, description: String, owner: Address, lang: Symbol) -> Result<u64, TaskError> {
+        Self::bump_ttl(&env);
+        owner.require_auth();
+
+        let task_id = Self::create_task(&env, description, owner, TaskStatus::Pending)?;
+        env.storage().instance().set(&OrgKey::TaskLang(task_id), &lang);
+        Ok(task_id)
+    }
+
+    // 119. LEER: Idioma de una tarea (None si se creó sin indicarlo)
+    pub fn get_task_lang(env: Env, task_id: u64) -> Option<Symbol> {
+        env.storage().instance().get(&OrgKey::TaskLang(task_id))
+    }
+
+    // 120. LEER: Tareas visibles de un propietario en un idioma (mismo criterio que 'get_tasks_by_owner')
+    pub fn get_tasks_by_owner_lang(env: Env, owner: Address, lang: Symbol) -> Vec<Task> {
+        Self::bump_ttl(&env);
+        let mut tasks = Vec::new(&env);
+        for task in Self::list_owner_tasks(&env, &owner).iter() {
+            if Self::get_task_lang(env.clone(), task.id) == Some(lang.clone()) {
+                tasks.push_back(task);
+            }
+        }
+        tasks
+    }
+
+    // 121. ACTUALIZAR: Color (0xRRGGBB) e icono con que las aplicaciones muestran una tarea
+    // None borra el valor correspondiente. Se devuelven en 'get_task_detail'.
+    pub fn set_task_appearance(env: Env, task_id: u64, caller: Address, color: Option<u32>, icon: Option<Symbol>) -> Result<(), TaskError> {
+        Self::bump_ttl(&env);
+        Self::load_task_for_update(&env, task_id, &caller, (task_id, color, icon.clone()).into_val(&env))?;
+
+        match color {
+            Some(color) if color > 0xFF_FFFF => return Err(TaskError::InvalidTaskData),
+            Some(color) => env.storage().instance().set(&OrgKey::TaskColor(task_id), &color),
+            None => env.storage().instance().remove(&OrgKey::TaskColor(task_id)),
+        }
+        match icon {
+            Some(icon) => env.storage().instance().set(&OrgKey::TaskIcon(task_id), &icon),
+            None => env.storage().instance().remove(&OrgKey::TaskIcon(task_id)),
+        }
+
+        Self::discard_undo(&env, task_id);
+        Self::emit_task_event(&env, symbol_short!("appear"), task_id);
+        Ok(())
+    }
+
+    // 122. ACTUALIZAR: Ubicar una tarea (latitud y longitud en millonésimas de grado)
+    pub fn set_task_location(env: Env, task_id: u64, caller: Address, lat: i32, lon: i32) -> Result<(), TaskError> {
+        Self::bump_ttl(&env);
+        Self::load_task_for_update(&env, task_id, &caller, (task_id, lat, lon).into_val(&env))?;
+
+        if !Self::is_valid_location(lat, lon) {
+            return Err(TaskError::InvalidTaskData);
+        }
+
+        Self::remove_location(&env, task_id);
+        env.storage().instance().set(&OrgKey::TaskLocation(task_id), &(lat, lon));
+
+        let cell_key = OrgKey::GeoCell(lat.div_euclid(GEO_CELL), lon.div_euclid(GEO_CELL));
+        let mut cell: Vec<u64> = Self::load_ids(&env, &cell_key);
+        cell.push_back(task_id);
+        env.storage().instance().set(&cell_key, &cell);
+
+        Self::discard_undo(&env, task_id);
+        Self::emit_task_event(&env, symbol_short!("located"), task_id);
+        Ok(())
+    }
+
+    // 123. ACTUALIZAR: Quitar la ubicación de una tarea
+    pub fn clear_task_location(env: Env, task_id: u64, caller: Address) -> Result<(), TaskError> {
+        Self::bump_ttl(&env);
+        Self::load_task_for_update(&env, task_id, &caller, (task_id,).into_val(&env))?;
+        Self::remove_location(&env, task_id);
+        Self::discard_undo(&env, task_id);
+        Ok(())
+    }
+
+    // 124. LEER: Ubicación de una tarea (None si no tiene)
+    pub fn get_task_location(env: Env, task_id: u64) -> Option<(i32, i32)> {
+        env.storage().instance().get(&OrgKey::TaskLocation(task_id))
+    }
+
+    // 125. LEER: Tareas pendientes de un propietario a menos de 'radius' (millonésimas de grado,
+    // como mucho MAX_NEAR_RADIUS) de un punto. La distancia es euclídea en grados, suficiente
+    // para recordatorios a escala de barrio.
+    pub fn get_tasks_near(env: Env, owner: Address, lat: i32, lon: i32, radius: i32) -> Result<Vec<Task>, TaskError> {
+        Self::bump_ttl(&env);
+        if radius <= 0 || radius > MAX_NEAR_RADIUS || !Self::is_valid_location(lat, lon) {
+            return Err(TaskError::InvalidTaskData);
+        }
+
+        let radius_sq = (radius as i64) * (radius as i64);
+        let mut tasks = Vec::new(&env);
+        for cell_lat in (lat - radius).div_euclid(GEO_CELL)..=(lat + radius).div_euclid(GEO_CELL) {
+            for cell_lon in (lon - radius).div_euclid(GEO_CELL)..=(lon + radius).div_euclid(GEO_CELL) {
+                let cell: Vec<u64> = Self::load_ids(&env, &OrgKey::GeoCell(cell_lat, cell_lon));
+                for task_id in cell.iter() {
+                    let (task_lat, task_lon): (i32, i32) = match Self::get_task_location(env.clone(), task_id) {
+                        Some(location) => location,
+                        None => continue,
+                    };
+                    let d_lat = (task_lat - lat) as i64;
+                    let d_lon = (task_lon - lon) as i64;
+                    if d_lat * d_lat + d_lon * d_lon > radius_sq {
+                        continue;
+                    }
+                    if let Some(task) = Self::load_task(&env, task_id) {
+                        if task.owner == owner && task.status == TaskStatus::Pending {
+                            tasks.push_back(task);
+                        }
+                    }
+                }
+            }
+        }
+        Ok(tasks)
+    }
+
+    // 126. ACTUALIZAR: Asignar (o quitar, con None) el contexto GTD de una tarea
+    pub fn set_task_context(env: Env, task_id: u64, caller: Address, context: Option<TaskContext>) -> Result<(), TaskError> {
+        Self::bump_ttl(&env);
+        let task = Self::load_task_for_update(&env, task_id, &caller, (task_id, context).into_val(&env))?;
+
+        Self::remove_context(&env, task_id, &task.owner);
+        if let Some(context) = context {
+            env.storage().instance().set(&OrgKey::TaskContext(task_id), &context);
+
+            let key = OrgKey::ContextTasks(task.owner, context);
+            let mut ids: Vec<u64> = Self::load_ids(&env, &key);
+            ids.push_back(task_id);
+            env.storage().instance().set(&key, &ids);
+        }
+
+        Self::discard_undo(&env, task_id);
+        Self::emit_task_event(&env, symbol_short!("context"), task_id);
+        Ok(())
+    }
+
+    // 127. LEER: Contexto GTD de una tarea (None si no tiene)
+    pub fn get_task_context(env: Env, task_id: u64) -> Option<TaskContext> {
+        env.storage().instance().get(&OrgKey::TaskContext(task_id))
+    }
+
+    // 128. LEER: Tareas pendientes de un propietario en un contexto (@casa, @recados...)
+    pub fn get_tasks_by_context(env: Env, owner: Address, context: TaskContext) -> Vec<Task> {
+        Self::bump_ttl(&env);
+        Self::list_context_tasks(&env, &owner, context)
+    }
+
+    // 129. LEER: Número de tareas pendientes por contexto (para los indicadores del panel)
+    pub fn get_context_counts(env: Env, owner: Address) -> Vec<(TaskContext, u32)> {
+        Self::bump_ttl(&env);
+        let mut counts = Vec::new(&env);
+        for context in [TaskContext::Home, TaskContext::Office, TaskContext::Errands, TaskContext::Phone, TaskContext::Computer] {
+            let pending = Self::list_context_tasks(&env, &owner, context).len();
+            counts.push_back((context, pending));
+        }
+        counts
+    }
+
+    // 130. ACTUALIZAR: Estimar el esfuerzo de una tarea (energía necesaria y minutos)
+    pub fn set_task_effort(env: Env, task_id: u64, caller: Address, energy: EnergyLevel, minutes: u32) -> Result<(), TaskError> {
+        Self::bump_ttl(&env);
+        let task = Self::load_task_for_update(&env, task_id, &caller, (task_id, energy, minutes).into_val(&env))?;
+
+        if minutes == 0 {
+            return Err(TaskError::InvalidTaskData);
+        }
+
+        Self::remove_effort(&env, task_id, &task.owner);
+        env.storage().instance().set(&OrgKey::TaskEffort(task_id), &TaskEffort { energy, minutes });
+
+        let key = OrgKey::EnergyTasks(task.owner, energy);
+        let mut ids: Vec<u64> = Self::load_ids(&env, &key);
+        ids.push_back(task_id);
+        env.storage().instance().set(&key, &ids);
+
+        Self::discard_undo(&env, task_id);
+        Self::emit_task_event(&env, symbol_short!("effort"), task_id);
+        Ok(())
+    }
+
+    // 131. LEER: Esfuerzo estimado de una tarea (None si no se ha estimado)
+    pub fn get_task_effort(env: Env, task_id: u64) -> Option<TaskEffort> {
+        env.storage().instance().get(&OrgKey::TaskEffort(task_id))
+    }
+
+    // 132. LEER: Sugerir una tarea pendiente que quepa en la energía y el tiempo disponibles
+    // Se prefieren las que aprovechan toda la energía disponible y, a igual energía, la más
+    // larga que quepa. Solo se consideran tareas con esfuerzo estimado.
+    pub fn suggest_task(env: Env, owner: Address, available_energy: EnergyLevel, available_minutes: u32) -> Option<Task> {
+        Self::bump_ttl(&env);
+        let levels: &[EnergyLevel] = match available_energy {
+            EnergyLevel::High => &[EnergyLevel::High, EnergyLevel::Medium, EnergyLevel::Low],
+            EnergyLevel::Medium => &[EnergyLevel::Medium, EnergyLevel::Low],
+            EnergyLevel::Low => &[EnergyLevel::Low],
+        };
+
+        for level in levels {
+            let ids: Vec<u64> = Self::load_ids(&env, &OrgKey::EnergyTasks(owner.clone(), *level));
+
+            let mut best: Option<(Task, u32)> = None;
+            for task_id in ids.iter() {
+                let effort = match Self::get_task_effort(env.clone(), task_id) {
+                    Some(effort) if effort.minutes <= available_minutes => effort,
+                    _ => continue,
+                };
+                if matches!(&best, Some((_, minutes)) if *minutes >= effort.minutes) {
+                    continue;
+                }
+                if let Some(task) = Self::load_task(&env, task_id) {
+                    // El índice no se actualiza al transferir: solo cuentan las que siguen siendo suyas
+                    if task.owner == owner && task.status == TaskStatus::Pending {
+                        best = Some((task, effort.minutes));
+                    }
+                }
+            }
+            if let Some((task, _)) = best {
+                return Some(task);
+            }
+        }
+        None
+    }
+
+    // 133. ACTUALIZAR: Poner una tarea pendiente a la espera de 'who' desde 'since' (epoch UNIX)
+    pub fn mark_waiting_on(env: Env, task_id: u64, caller: Address, who: String, since: u64) -> Result<(), TaskError> {
+        Self::bump_ttl(&env);
+        let mut task = Self::load_task_for_update(&env, task_id, &caller, (task_id, who.clone(), since).into_val(&env))?;
+
+        if who.is_empty() || since > env.ledger().timestamp() {
+            return Err(TaskError::InvalidTaskData);
+        }
+        let old_status = Self::transition(&mut task, TaskStatus::WaitingOn)?;
+
+        Self::save_task(&env, &mut task);
+        env.storage().instance().set(&OrgKey::WaitingOn(task_id), &(who, since));
+        Self::discard_undo(&env, task_id);
+        Self::emit_status_event(&env, symbol_short!("waiting"), task_id, old_status, TaskStatus::WaitingOn);
+        Ok(())
+    }
+
+    // 134. ACTUALIZAR: Dejar de esperar (la tarea vuelve a Pending)
+    pub fn stop_waiting(env: Env, task_id: u64, caller: Address) -> Result<(), TaskError> {
+        Self::bump_ttl(&env);
+        let mut task = Self::load_task_for_update(&env, task_id, &caller, (task_id,).into_val(&env))?;
+
+        if task.status != TaskStatus::WaitingOn {
+            return Err(TaskError::InvalidTaskData);
+        }
+        Self::transition(&mut task, TaskStatus::Pending)?;
+
+        Self::save_task(&env, &mut task);
+        env.storage().instance().remove(&OrgKey::WaitingOn(task_id));
+        Self::discard_undo(&env, task_id);
+        Self::emit_status_event(&env, symbol_short!("resumed"), task_id, TaskStatus::WaitingOn, TaskStatus::Pending);
+        Ok(())
+    }
+
+    // 135. LEER: Tareas de un propietario a la espera de terceros (revisión semanal GTD)
+    pub fn get_waiting(env: Env, owner: Address) -> Vec<WaitingItem> {
+        Self::bump_ttl(&env);
+        let mut items = Vec::new(&env);
+        for task in Self::list_owner_tasks(&env, &owner).iter() {
+            if task.owner != owner || task.status != TaskStatus::WaitingOn {
+                continue;
+            }
+            if let Some((who, since)) = env.storage().instance().get::<OrgKey, (String, u64)>(&OrgKey::WaitingOn(task.id)) {
+                items.push_back(WaitingItem { task, who, since });
+            }
+        }
+        items
+    }
+
+    // 136. ACTUALIZAR: Aparcar una tarea pendiente en el backlog (algún día / quizá)
+    // Deja de contar como abierta y sale de los listados habituales.
+    pub fn move_to_backlog(env: Env, task_id: u64, caller: Address) -> Result<(), TaskError> {
+        Self::bump_ttl(&env);
+        let mut task = Self::load_task_for_update(&env, task_id, &caller, (task_id,).into_val(&env))?;
+
+        let old_status = Self::transition(&mut task, TaskStatus::Backlog)?;
+        Self::record_day_activity(&env, &task.owner, false, false);
+        Self::track_due(&env, &task, false);
+
+        Self::save_task(&env, &mut task);
+        Self::emit_status_event(&env, symbol_short!("backlog"), task_id, old_status, TaskStatus::Backlog);
+        Ok(())
+    }
+
+    // 137. ACTUALIZAR: Recuperar una tarea del backlog (vuelve a Pending)
+    // Vuelve a contar como abierta, pero no como creada.
+    pub fn promote_from_backlog(env: Env, task_id: u64, caller: Address) -> Result<(), TaskError> {
+        Self::bump_ttl(&env);
+        let mut task = Self::load_task_for_update(&env, task_id, &caller, (task_id,).into_val(&env))?;
+
+        if task.status != TaskStatus::Backlog {
+            return Err(TaskError::InvalidTaskData);
+        }
+        Self::transition(&mut task, TaskStatus::Pending)?;
+        let (key, stats) = Self::adjust_open_count(&env, &task.owner, true);
+        env.storage().instance().set(&key, &stats);
+        Self::track_due(&env, &task, true);
+
+        Self::save_task(&env, &mut task);
+        Self::emit_status_event(&env, symbol_short!("promoted"), task_id, TaskStatus::Backlog, TaskStatus::Pending);
+        Ok(())
+    }
+
+    // 138. LEER: Tareas aparcadas en el backlog de un propietario
+    pub fn get_backlog(env: Env, owner: Address) -> Vec<Task> {
+        Self::bump_ttl(&env);
+        let task_ids: Vec<u64> = Self::load_ids(&env, &owner);
+
+        let mut tasks = Vec::new(&env);
+        for task_id in task_ids.iter() {
+            if let Some(task) = Self::load_task(&env, task_id) {
+                if task.owner == owner && task.status == TaskStatus::Backlog {
+                    tasks.push_back(task);
+                }
+            }
+        }
+        tasks
+    }
+
+    // 139. ACTUALIZAR: Revisar una tarea cada 'every_secs' segundos (0 deja de revisarla)
+    // La cuenta empieza ahora, como si se acabara de revisar.
+    pub fn set_review_cadence(env: Env, task_id: u64, caller: Address, every_secs: u64) -> Result<(), TaskError> {
+        Self::bump_ttl(&env);
+        let task = Self::load_task_for_update(&env, task_id, &caller, (task_id, every_secs).into_val(&env))?;
+
+        Self::remove_review(&env, task_id, &task.owner);
+        if every_secs > 0 {
+            let schedule = ReviewSchedule {
+                every_secs,
+                last_reviewed_at: env.ledger().timestamp(),
+            };
+            env.storage().instance().set(&OrgKey::Review(task_id), &schedule);
+
+            let key = OrgKey::ReviewTasks(task.owner);
+            let mut ids: Vec<u64> = Self::load_ids(&env, &key);
+            ids.push_back(task_id);
+            env.storage().instance().set(&key, &ids);
+        }
+        Self::discard_undo(&env, task_id);
+        Ok(())
+    }
+
+    // 140. ACTUALIZAR: Anotar que se ha revisado una tarea (reinicia su cuenta)
+    pub fn mark_reviewed(env: Env, task_id: u64, caller: Address) -> Result<(), TaskError> {
+        Self::bump_ttl(&env);
+        Self::load_task_for_update(&env, task_id, &caller, (task_id,).into_val(&env))?;
+
+        let key = OrgKey::Review(task_id);
+        let mut schedule: ReviewSchedule = env.storage().instance().get(&key).ok_or(TaskError::InvalidTaskData)?;
+        schedule.last_reviewed_at = env.ledger().timestamp();
+        env.storage().instance().set(&key, &schedule);
+
+        Self::emit_task_event(&env, symbol_short!("reviewed"), task_id);
+        Ok(())
+    }
+
+    // 141. LEER: Revisión periódica de una tarea (None si no tiene)
+    pub fn get_review_schedule(env: Env, task_id: u64) -> Option<ReviewSchedule> {
+        env.storage().instance().get(&OrgKey::Review(task_id))
+    }
+
+    // 142. LEER: Hasta 'limit' tareas de un propietario cuya revisión ya toca (abiertas o en el
+    // backlog), de la que más tiempo lleva vencida a la que menos
+    pub fn get_due_for_review(env: Env, owner: Address, limit: u32) -> Vec<Task> {
+        Self::bump_ttl(&env);
+        let now = env.ledger().timestamp();
+        let ids: Vec<u64> = Self::load_ids(&env, &OrgKey::ReviewTasks(owner.clone()));
+
+        // Ordenadas por momento en que venció la revisión (inserción ordenada)
+        let mut due: Vec<(u64, u64)> = Vec::new(&env);
+        for task_id in ids.iter() {
+            let schedule = match Self::get_review_schedule(env.clone(), task_id) {
+                Some(schedule) => schedule,
+                None => continue,
+            };
+            let due_at = schedule.last_reviewed_at.saturating_add(schedule.every_secs);
+            if due_at > now {
+                continue;
+            }
+            let pos = match due.binary_search((due_at, task_id)) {
+                Ok(pos) | Err(pos) => pos,
+            };
+            due.insert(pos, (due_at, task_id));
+        }
+
+        let mut tasks = Vec::new(&env);
+        for (_, task_id) in due.iter() {
+            if tasks.len() == limit {
+                break;
+            }
+            if let Some(task) = Self::load_task(&env, task_id) {
+                if task.owner == owner && (Self::is_open(&task.status) || task.status == TaskStatus::Backlog) {
+                    tasks.push_back(task);
+                }
+            }
+        }
+        tasks
+    }
+
+    // 143. DEPENDENCIAS: Declarar que 'task_id' depende de 'depends_on'
+    // Si 'depends_on' es de otro propietario queda como solicitud hasta que él la apruebe con
+    // 'approve_external_dependency'. Retorna true si la dependencia ya está activa.
+    pub fn add_dependency(env: Env, task_id: u64, caller: Address, depends_on: u64) -> Result<bool, TaskError> {
+        Self::bump_ttl(&env);
+        let task = Self::load_task_for_update(&env, task_id, &caller, (task_id, depends_on).into_val(&env))?;
+        let upstream = Self::load_task(&env, depends_on).ok_or(TaskError::TaskNotFound)?;
+
+        if task_id == depends_on || upstream.status == TaskStatus::Deleted {
+            return Err(TaskError::InvalidTaskData);
+        }
+        let dependencies = Self::get_dependencies(env.clone(), task_id);
+        if dependencies.contains(depends_on) || dependencies.len() >= MAX_DEPENDENCIES {
+            return Err(TaskError::InvalidTaskData);
+        }
+        Self::ensure_no_cycle(&env, task_id, depends_on)?;
+
+        if upstream.owner == task.owner {
+            Self::activate_dependency(&env, task_id, depends_on);
+            return Ok(true);
+        }
+
+        let key = OrgKey::DependencyRequests(depends_on);
+        let mut requests: Vec<u64> = Self::load_ids(&env, &key);
+        if !requests.contains(task_id) {
+            requests.push_back(task_id);
+            env.storage().instance().set(&key, &requests);
+        }
+        Self::emit_task_event(&env, symbol_short!("dep_req"), depends_on);
+        Ok(false)
+    }
+
+    // 144. DEPENDENCIAS: El propietario de 'task_id' acepta que 'dependent_id' dependa de ella
+    pub fn approve_external_dependency(env: Env, task_id: u64, caller: Address, dependent_id: u64) -> Result<(), TaskError> {
+        Self::bump_ttl(&env);
+        Self::load_task_for_update(&env, task_id, &caller, (task_id, dependent_id).into_val(&env))?;
+
+        let key = OrgKey::DependencyRequests(task_id);
+        let mut requests: Vec<u64> = Self::load_ids(&env, &key);
+        let i = requests.first_index_of(dependent_id).ok_or(TaskError::InvalidTaskData)?;
+        requests.remove(i);
+        env.storage().instance().set(&key, &requests);
+
+        // El grafo pudo cambiar desde la solicitud: mismas comprobaciones que 'add_dependency'
+        let dependent = Self::load_task(&env, dependent_id).ok_or(TaskError::TaskNotFound)?;
+        if dependent.status == TaskStatus::Deleted {
+            return Err(TaskError::InvalidTaskData);
+        }
+        let dependencies = Self::get_dependencies(env.clone(), dependent_id);
+        if dependencies.contains(task_id) || dependencies.len() >= MAX_DEPENDENCIES {
+            return Err(TaskError::InvalidTaskData);
+        }
+        Self::ensure_no_cycle(&env, dependent_id, task_id)?;
+        Self::activate_dependency(&env, dependent_id, task_id);
+        Ok(())
+    }
+
+    // 145. LEER: Tareas de las que depende una tarea (solo dependencias activas)
+    pub fn get_dependencies(env: Env, task_id: u64) -> Vec<u64> {
+        env.storage().instance().get(&OrgKey::Dependencies(task_id)).unwrap_or(Vec::new(&env))
+    }
+
+    // 146. LEER: Tareas que esperan a esta (para que su propietario vea quién depende de él)
+    pub fn get_dependents(env: Env, task_id: u64) -> Vec<u64> {
+        env.storage().instance().get(&OrgKey::Dependents(task_id)).unwrap_or(Vec::new(&env))
+    }
+
+    // 147. LEER: Solicitudes de otros propietarios para depender de esta tarea
+    pub fn get_dependency_requests(env: Env, task_id: u64) -> Vec<u64> {
+        env.storage().instance().get(&OrgKey::DependencyRequests(task_id)).unwrap_or(Vec::new(&env))
+    }
+
+    // 148. PLANIFICACIÓN: Camino crítico de un objetivo (proyecto), del principio al final
+    // Considera sus tareas abiertas, la duración estimada con 'set_task_effort' (0 si no hay)
+    // y las dependencias entre ellas. Como mucho MAX_PROJECT_TASKS tareas.
+    pub fn get_critical_path(env: Env, project_id: u32) -> Result<Vec<u64>, TaskError> {
+        Self::bump_ttl(&env);
+        let tasks = Self::project_tasks(&env, project_id)?;
+        let (ids, _, finish, previous) = Self::schedule_project(&env, &tasks);
+
+        // Recorrer hacia atrás desde la tarea que termina más tarde
+        let mut path = Vec::new(&env);
+        let mut last: Option<u32> = None;
+        for i in 0..ids.len() {
+            let later = match last {
+                Some(l) => finish.get_unchecked(i) > finish.get_unchecked(l),
+                None => true,
+            };
+            if later {
+                last = Some(i);
+            }
+        }
+        while let Some(i) = last {
+            path.push_front(ids.get_unchecked(i));
+            last = match previous.get_unchecked(i) {
+                0 => None,
+                dep => ids.first_index_of(dep),
+            };
+        }
+        Ok(path)
+    }
+
+    // 149. PLANIFICACIÓN: Datos para un diagrama de Gantt de un objetivo (proyecto)
+    // Una entrada por tarea vinculada. El inicio estimado parte de ahora y encadena las
+    // duraciones estimadas según las dependencias; las tareas ya cerradas tienen inicio 0.
+    pub fn get_timeline(env: Env, project_id: u32) -> Result<Vec<TimelineEntry>, TaskError> {
+        Self::bump_ttl(&env);
+        let tasks = Self::project_tasks(&env, project_id)?;
+        let (ids, durations, finish, _) = Self::schedule_project(&env, &tasks);
+        let now = env.ledger().timestamp();
+
+        let mut timeline = Vec::new(&env);
+        for task in tasks.iter() {
+            let (start_estimate, minutes) = match ids.first_index_of(task.id) {
+                Some(i) => {
+                    let minutes = durations.get_unchecked(i);
+                    (now + (finish.get_unchecked(i) - minutes) * 60, minutes)
+                }
+                None => (0, 0),
+            };
+            timeline.push_back(TimelineEntry {
+                task_id: task.id,
+                start_estimate,
+                duration_minutes: minutes as u32,
+                due_date: task.due_date.unwrap_or(0),
+                dependencies: Self::get_dependencies(env.clone(), task.id),
+                status: task.status,
+            });
+        }
+        Ok(timeline)
+    }
+
+    // 150. DIVIDIR: Crear una subtarea por cada descripción de 'parts' (de 2 a MAX_SPLIT_PARTS)
+    // Todo en la misma transacción: si una parte no es válida no se crea ninguna. Según 'mode'
+    // la original va a la papelera o queda como contenedor. Retorna los IDs de las subtareas.
+    pub fn split_task(env: Env, task_id: u64, caller: Address, parts: Vec<String>, mode: SplitMode) -> Result<Vec<u64>, TaskError> {
+        Self::bump_ttl(&env);
+        let task = Self::load_task_for_update(&env, task_id, &caller, (task_id, parts.clone(), mode).into_val(&env))?;
+
+        if parts.len() < 2 || parts.len() > MAX_SPLIT_PARTS {
+            return Err(TaskError::InvalidTaskData);
+        }
+        if task.status != TaskStatus::Pending {
+            return Err(TaskError::TaskAlreadyCompleted);
+        }
+
+        let mut subtasks = Self::get_subtasks(env.clone(), task_id);
+        let mut created = Vec::new(&env);
+        for description in parts.iter() {
+            let child_id = Self::create_task(&env, description, task.owner.clone(), TaskStatus::Pending)?;
+            env.storage().instance().set(&OrgKey::ParentTask(child_id), &task_id);
+            subtasks.push_back(child_id);
+            created.push_back(child_id);
+        }
+        env.storage().instance().set(&OrgKey::Subtasks(task_id), &subtasks);
+        Self::emit_task_event(&env, symbol_short!("split"), task_id);
+
+        if mode == SplitMode::Replace {
+            Self::soft_delete(&env, task)?;
+        }
+        Ok(created)
+    }
+
+    // 151. LEER: Subtareas creadas al dividir una tarea
+    pub fn get_subtasks(env: Env, task_id: u64) -> Vec<u64> {
+        env.storage().instance().get(&OrgKey::Subtasks(task_id)).unwrap_or(Vec::new(&env))
+    }
+
+    // 152. LEER: Tarea de la que salió una subtarea (None si no viene de una división)
+    pub fn get_parent_task(env: Env, task_id: u64) -> Option<u64> {
+        env.storage().instance().get(&OrgKey::ParentTask(task_id))
+    }
+
+    // 153. FUSIONAR: Unir una tarea duplicada con la principal (del mismo propietario)
+    // La descripción de la duplicada se añade a la de la principal (" | " entre ambas, como
+    // mucho MAX_DESCRIPTION_LEN bytes) y la duplicada va a la papelera. Quedan enlazadas en
+    // ambos sentidos ('get_merged_into' / 'get_merged_from').
+    pub fn merge_tasks(env: Env, primary_id: u64, duplicate_id: u64, caller: Address) -> Result<(), TaskError> {
+        Self::bump_ttl(&env);
+        // Manda la duplicada a la papelera: hace falta la autoridad del propietario, no basta un editor
+        let mut primary = Self::load_task_for_update(&env, primary_id, &caller, (primary_id, duplicate_id).into_val(&env))?;
+        let duplicate = Self::load_task(&env, duplicate_id).ok_or(TaskError::TaskNotFound)?;
+
+        if primary_id == duplicate_id {
+            return Err(TaskError::InvalidTaskData);
+        }
+        if duplicate.owner != primary.owner {
+            return Err(TaskError::Unauthorized);
+        }
+        Self::ensure_not_frozen(&env, duplicate_id)?;
+        Self::ensure_not_locked(&env, duplicate_id, &caller)?;
+        if primary.status != TaskStatus::Pending {
+            return Err(TaskError::TaskAlreadyCompleted);
+        }
+
+        primary.description = Self::join_descriptions(&env, &primary.description, &duplicate.description)?;
+        Self::save_task(&env, &mut primary);
+        Self::soft_delete(&env, duplicate)?;
+
+        env.storage().instance().set(&OrgKey::MergedInto(duplicate_id), &primary_id);
+        let mut merged = Self::get_merged_from(env.clone(), primary_id);
+        merged.push_back(duplicate_id);
+        env.storage().instance().set(&OrgKey::MergedFrom(primary_id), &merged);
+
+        Self::emit_task_event(&env, symbol_short!("merged"), primary_id);
+        Ok(())
+    }
+
+    // 154. LEER: Tarea principal con la que se fusionó una duplicada
+    pub fn get_merged_into(env: Env, task_id: u64) -> Option<u64> {
+        env.storage().instance().get(&OrgKey::MergedInto(task_id))
+    }
+
+    // 155. LEER: Duplicadas fusionadas en una tarea principal
+    pub fn get_merged_from(env: Env, task_id: u64) -> Vec<u64> {
+        env.storage().instance().get(&OrgKey::MergedFrom(task_id)).unwrap_or(Vec::new(&env))
+    }
+
+    // 156. ENLAZAR: Relacionar dos tareas del mismo propietario
+    // El enlace se guarda en ambas tareas; si ya estaban enlazadas se cambia la relación.
+    pub fn link_tasks(env: Env, a: u64, b: u64, caller: Address, relation: LinkType) -> Result<(), TaskError> {
+        Self::bump_ttl(&env);
+        let task = Self::load_task_for_update(&env, a, &caller, (a, b, relation).into_val(&env))?;
+        let other = Self::load_task(&env, b).ok_or(TaskError::TaskNotFound)?;
+
+        if a == b || other.status == TaskStatus::Deleted {
+            return Err(TaskError::InvalidTaskData);
+        }
+        if other.owner != task.owner {
+            return Err(TaskError::Unauthorized);
+        }
+
+        let outgoing = TaskLink { task_id: b, relation, outgoing: true };
+        let incoming = TaskLink { task_id: a, relation, outgoing: false };
+        if Self::get_links(env.clone(), a).contains(&outgoing) {
+            return Err(TaskError::NoChange);
+        }
+        Self::store_link(&env, a, outgoing)?;
+        Self::store_link(&env, b, incoming)?;
+
+        Self::emit_task_event(&env, symbol_short!("linked"), a);
+        Ok(())
+    }
+
+    // 157. LEER: Enlaces de una tarea (en ambos sentidos)
+    pub fn get_links(env: Env, task_id: u64) -> Vec<TaskLink> {
+        env.storage().instance().get(&OrgKey::TaskLinks(task_id)).unwrap_or(Vec::new(&env))
+    }
+
+    // 158. LEER: Raíz Merkle por tramos de como mucho 'limit' IDs (<= MAX_PAGE_SIZE) desde 'start'
+    // (o desde el 1), para tableros demasiado grandes para 'get_state_root'. La primera llamada
+    // pasa un 'frontier' vacío; las siguientes, el 'frontier' y el 'next_cursor' recibidos.
+    // El resultado final coincide con 'get_state_root'.
+    pub fn get_state_root_page(env: Env, start: u64, limit: u32, frontier: Vec<BytesN<32>>) -> Result<StateRootPage, TaskError> {
+        Self::bump_ttl(&env);
+        let last_id = Self::get_next_task_id(&env);
+        let start = start.max(1);
+        if limit == 0 || limit > MAX_PAGE_SIZE || start > last_id {
+            return Err(TaskError::InvalidTaskData);
+        }
+        // El acumulador tiene un nivel por cada bit del número de hojas ya procesadas
+        if frontier.len() != 64 - (start - 1).leading_zeros() {
+            return Err(TaskError::InvalidTaskData);
+        }
+
+        let mut frontier = frontier;
+        let end = start.saturating_add(u64::from(limit)).min(last_id);
+        for id in start..end {
+            Self::merkle_push(&env, &mut frontier, id - 1, Self::merkle_leaf(&env, id));
+        }
+
+        if end < last_id {
+            return Ok(StateRootPage { frontier, next_cursor: Some(end), root: None });
+        }
+        let root = Self::merkle_finish(&env, &frontier, last_id - 1);
+        Ok(StateRootPage { frontier, next_cursor: None, root: Some(root) })
+    }
+
+    // 159. POMODORO: Abandonar la sesión en curso de una tarea sin acumular tiempo de concentración
+    pub fn cancel_pomodoro(env: Env, task_id: u64, caller: Address) -> Result<(), TaskError> {
+        Self::bump_ttl(&env);
+        Self::load_task_for_update(&env, task_id, &caller, (task_id,).into_val(&env))?;
+
+        let key = DataKey::ActivePomodoro(task_id);
+        if !env.storage().instance().has(&key) {
+            return Err(TaskError::NoActivePomodoro);
+        }
+        env.storage().instance().remove(&key);
+        Ok(())
+    }
+
+
+    /// Función helper: guarda 'link' en 'task_id', sustituyendo el enlace previo con la misma tarea
+    fn store_link(env: &Env, task_id: u64, link: TaskLink) -> Result<(), TaskError> {
+        let mut links = Self::get_links(env.clone(), task_id);
+        match links.iter().position(|l| l.task_id == link.task_id) {
+            Some(i) => links.set(i as u32, link),
+            None if links.len() >= MAX_TASK_LINKS => return Err(TaskError::InvalidTaskData),
+            None => links.push_back(link),
+        }
+        env.storage().instance().set(&OrgKey::TaskLinks(task_id), &links);
+        Ok(())
+    }
+
+    /// Función helper: borra los enlaces de una tarea y su extremo en las tareas enlazadas
+    fn remove_links(env: &Env, task_id: u64) {
+        for link in Self::get_links(env.clone(), task_id).iter() {
+            let mut links = Self::get_links(env.clone(), link.task_id);
+            if let Some(i) = links.iter().position(|l| l.task_id == task_id) {
+                links.remove(i as u32);
+                env.storage().instance().set(&OrgKey::TaskLinks(link.task_id), &links);
+            }
+        }
+        env.storage().instance().remove(&OrgKey::TaskLinks(task_id));
+    }
+
+    /// Función helper: concatena dos descripciones con " | " (como mucho MAX_DESCRIPTION_LEN bytes)
+    fn join_descriptions(env: &Env, first: &String, second: &String) -> Result<String, TaskError> {
+        const SEPARATOR: &[u8] = b" | ";
+        let (first_len, second_len) = (first.len() as usize, second.len() as usize);
+        let total = first_len + SEPARATOR.len() + second_len;
+        if total > MAX_DESCRIPTION_LEN {
+            return Err(TaskError::InvalidTaskData);
+        }
+
+        let mut buf = [0u8; MAX_DESCRIPTION_LEN];
+        first.copy_into_slice(&mut buf[..first_len]);
+        buf[first_len..first_len + SEPARATOR.len()].copy_from_slice(SEPARATOR);
+        second.copy_into_slice(&mut buf[first_len + SEPARATOR.len()..total]);
+
+        let text = core::str::from_utf8(&buf[..total]).map_err(|_| TaskError::InvalidTaskData)?;
+        Ok(String::from_str(env, text))
+    }
+
+    /// Función helper: planificación de las tareas abiertas de un proyecto. Retorna sus IDs,
+    /// duraciones estimadas (minutos), fin más temprano (minutos desde ahora) y la dependencia
+    /// que lo determina (0 si ninguna), en vectores paralelos
+    fn schedule_project(env: &Env, tasks: &Vec<Task>) -> (Vec<u64>, Vec<u64>, Vec<u64>, Vec<u64>) {
+        let mut ids: Vec<u64> = Vec::new(env);
+        let mut durations: Vec<u64> = Vec::new(env);
+        let mut previous: Vec<u64> = Vec::new(env);
+        for task in tasks.iter() {
+            if Self::is_open(&task.status) {
+                ids.push_back(task.id);
+                durations.push_back(Self::get_task_effort(env.clone(), task.id).map_or(0, |effort| effort.minutes as u64));
+                previous.push_back(0);
+            }
+        }
+
+        // Dependencias dentro del proyecto, como posiciones en 'ids': se leen una sola vez
+        let mut deps: Vec<Vec<u32>> = Vec::new(env);
+        for id in ids.iter() {
+            let mut positions = Vec::new(env);
+            for dep in Self::get_dependencies(env.clone(), id).iter() {
+                if let Some(j) = ids.first_index_of(dep) {
+                    positions.push_back(j);
+                }
+            }
+            deps.push_back(positions);
+        }
+
+        // Fin más temprano de cada tarea = su duración + el mayor fin de sus dependencias.
+        // El grafo no tiene ciclos, así que basta con relajar hasta que nada cambie.
+        let mut finish = durations.clone();
+        for _ in ids.iter() {
+            let mut changed = false;
+            for i in 0..ids.len() {
+                for j in deps.get_unchecked(i).iter() {
+                    let candidate = finish.get_unchecked(j) + durations.get_unchecked(i);
+                    if candidate > finish.get_unchecked(i) {
+                        finish.set(i, candidate);
+                        previous.set(i, ids.get_unchecked(j));
+                        changed = true;
+                    }
+                }
+            }
+            if !changed {
+                break;
+            }
+        }
+        (ids, durations, finish, previous)
+    }
+
+    /// Función helper: tareas vinculadas a un objetivo (proyecto), como mucho MAX_PROJECT_TASKS
+    fn project_tasks(env: &Env, project_id: u32) -> Result<Vec<Task>, TaskError> {
+        let goal: Goal = env.storage().instance().get(&DataKey::Goal(project_id)).ok_or(TaskError::GoalNotFound)?;
+        let task_ids: Vec<u64> = Self::load_ids(env, &goal.owner);
+
+        let mut tasks = Vec::new(env);
+        for task_id in task_ids.iter() {
+            if env.storage().instance().get::<DataKey, u32>(&DataKey::TaskGoal(task_id)) != Some(project_id) {
+                continue;
+            }
+            if let Some(task) = Self::load_task(env, task_id) {
+                if tasks.len() == MAX_PROJECT_TASKS {
+                    return Err(TaskError::InvalidTaskData);
+                }
+                tasks.push_back(task);
+            }
+        }
+        Ok(tasks)
+    }
+
+    /// Función helper: registra la dependencia en ambos sentidos
+    fn activate_dependency(env: &Env, task_id: u64, depends_on: u64) {
+        let mut dependencies = Self::get_dependencies(env.clone(), task_id);
+        dependencies.push_back(depends_on);
+        env.storage().instance().set(&OrgKey::Dependencies(task_id), &dependencies);
+
+        let mut dependents = Self::get_dependents(env.clone(), depends_on);
+        dependents.push_back(task_id);
+        env.storage().instance().set(&OrgKey::Dependents(depends_on), &dependents);
+
+        Self::emit_task_event(env, symbol_short!("depends"), task_id);
+    }
+
+    /// Función helper: borra las dependencias de una tarea y la saca de las listas de las demás
+    fn remove_dependencies(env: &Env, task_id: u64) {
+        for upstream in Self::get_dependencies(env.clone(), task_id).iter() {
+            let mut dependents = Self::get_dependents(env.clone(), upstream);
+            if let Some(i) = dependents.first_index_of(task_id) {
+                dependents.remove(i);
+                env.storage().instance().set(&OrgKey::Dependents(upstream), &dependents);
+            }
+        }
+        for dependent in Self::get_dependents(env.clone(), task_id).iter() {
+            let mut dependencies = Self::get_dependencies(env.clone(), dependent);
+            if let Some(i) = dependencies.first_index_of(task_id) {
+                dependencies.remove(i);
+                env.storage().instance().set(&OrgKey::Dependencies(dependent), &dependencies);
+            }
+        }
+        env.storage().instance().remove(&OrgKey::Dependencies(task_id));
+        env.storage().instance().remove(&OrgKey::Dependents(task_id));
+        env.storage().instance().remove(&OrgKey::DependencyRequests(task_id));
+    }
+
+    /// Función helper: falla si 'depends_on' ya depende (directa o indirectamente) de 'task_id'
+    /// Recorre como mucho MAX_DEPENDENCY_SCAN tareas; más allá se rechaza por prudencia.
+    fn ensure_no_cycle(env: &Env, task_id: u64, depends_on: u64) -> Result<(), TaskError> {
+        let mut pending: Vec<u64> = Vec::from_array(env, [depends_on]);
+        let mut seen: Vec<u64> = Vec::new(env);
+
+        while let Some(current) = pending.pop_back() {
+            if current == task_id {
+                return Err(TaskError::DependencyCycle);
+            }
+            if seen.contains(current) {
+                continue;
+            }
+            if seen.len() >= MAX_DEPENDENCY_SCAN {
+                return Err(TaskError::DependencyCycle);
+            }
+            seen.push_back(current);
+            pending.append(&Self::get_dependencies(env.clone(), current));
+        }
+        Ok(())
+    }
+
+    /// Función helper: quita la revisión periódica de una tarea y la saca del índice del propietario
+    fn remove_review(env: &Env, task_id: u64, owner: &Address) {
+        if !env.storage().instance().has(&OrgKey::Review(task_id)) {
+            return;
+        }
+        env.storage().instance().remove(&OrgKey::Review(task_id));
+
+        let key = OrgKey::ReviewTasks(owner.clone());
+        let mut ids: Vec<u64> = Self::load_ids(env, &key);
+        if let Some(i) = ids.first_index_of(task_id) {
+            ids.remove(i);
+            env.storage().instance().set(&key, &ids);
+        }
+    }
+
+    /// Función helper: quita el esfuerzo estimado de una tarea y la saca del índice de energía
+    fn remove_effort(env: &Env, task_id: u64, owner: &Address) {
+        let effort: TaskEffort = match env.storage().instance().get(&OrgKey::TaskEffort(task_id)) {
+            Some(effort) => effort,
+            None => return,
+        };
+        env.storage().instance().remove(&OrgKey::TaskEffort(task_id));
+
+        let key = OrgKey::EnergyTasks(owner.clone(), effort.energy);
+        let mut ids: Vec<u64> = Self::load_ids(env, &key);
+        if let Some(i) = ids.first_index_of(task_id) {
+            ids.remove(i);
+            env.storage().instance().set(&key, &ids);
+        }
+    }
+
+    /// Función helper: quita el contexto de una tarea y la saca del índice de su propietario
+    fn remove_context(env: &Env, task_id: u64, owner: &Address) {
+        let context: TaskContext = match env.storage().instance().get(&OrgKey::TaskContext(task_id)) {
+            Some(context) => context,
+            None => return,
+        };
+        env.storage().instance().remove(&OrgKey::TaskContext(task_id));
+
+        let key = OrgKey::ContextTasks(owner.clone(), context);
+        let mut ids: Vec<u64> = Self::load_ids(env, &key);
+        if let Some(i) = ids.first_index_of(task_id) {
+            ids.remove(i);
+            env.storage().instance().set(&key, &ids);
+        }
+    }
+
+    /// Función helper: coordenadas dentro de rango (millonésimas de grado)
+    fn is_valid_location(lat: i32, lon: i32) -> bool {
+        (-90_000_000..=90_000_000).contains(&lat) && (-180_000_000..=180_000_000).contains(&lon)
+    }
+
+    /// Función helper: borra la ubicación de una tarea y la saca de su celda de la rejilla
+    fn remove_location(env: &Env, task_id: u64) {
+        let (lat, lon): (i32, i32) = match env.storage().instance().get(&OrgKey::TaskLocation(task_id)) {
+            Some(location) => location,
+            None => return,
+        };
+        env.storage().instance().remove(&OrgKey::TaskLocation(task_id));
+
+        let cell_key = OrgKey::GeoCell(lat.div_euclid(GEO_CELL), lon.div_euclid(GEO_CELL));
+        let mut cell: Vec<u64> = Self::load_ids(env, &cell_key);
+        if let Some(i) = cell.first_index_of(task_id) {
+            cell.remove(i);
+            if cell.is_empty() {
+                env.storage().instance().remove(&cell_key);
+            } else {
+                env.storage().instance().set(&cell_key, &cell);
+            }
+        }
+    }
+
+    /// Función helper: claves auxiliares que puede tener una tarea además de su registro
+    fn task_side_keys(env: &Env, task_id: u64) -> Vec<DataKey> {
+        soroban_sdk::vec![
+            env,
+            DataKey::FrozenTask(task_id),
+            DataKey::ScheduledFor(task_id),
+            DataKey::AgedAt(task_id),
+            DataKey::BlockedReason(task_id),
+            DataKey::ActivePomodoro(task_id),
+            DataKey::TaskFocus(task_id),
+            DataKey::Habit(task_id),
+            DataKey::TaskGoal(task_id),
+            DataKey::DeletedAt(task_id),
+            DataKey::UndoSnapshot(task_id),
+            DataKey::Recovery(task_id),
+            DataKey::Hidden(task_id),
+            DataKey::DescriptionHash(task_id),
+            DataKey::EncryptedDescription(task_id),
+            DataKey::CompletionCommitment(task_id),
+            DataKey::CompletionProof(task_id),
+            DataKey::TaskOracle(task_id),
+            DataKey::TaskSeq(task_id),
+            DataKey::Cold(task_id),
+        ]
+    }
+
+    /// Función helper: claves de organización que puede tener una tarea
+    fn task_org_keys(env: &Env, task_id: u64) -> Vec<OrgKey> {
+        soroban_sdk::vec![
+            env,
+            OrgKey::TaskLang(task_id),
+            OrgKey::TaskColor(task_id),
+            OrgKey::TaskIcon(task_id),
+            OrgKey::TaskLocation(task_id),
+            OrgKey::TaskContext(task_id),
+            OrgKey::TaskEffort(task_id),
+            OrgKey::WaitingOn(task_id),
+            OrgKey::Review(task_id),
+            OrgKey::Dependencies(task_id),
+            OrgKey::Dependents(task_id),
+            OrgKey::DependencyRequests(task_id),
+            OrgKey::Subtasks(task_id),
+            OrgKey::ParentTask(task_id),
+            OrgKey::MergedInto(task_id),
+            OrgKey::MergedFrom(task_id),
+            OrgKey::TaskLinks(task_id),
+        ]
+    }
+
+    /// Función helper: cuántas de las claves existen en el almacenamiento de instancia
+    fn count_present<K: IntoVal<Env, Val> + TryFromVal<Env, Val> + Clone>(env: &Env, keys: Vec<K>) -> u32 {
+        let mut count: u32 = 0;
+        for key in keys.iter() {
+            if env.storage().instance().has(&key) {
+                count += 1;
+            }
+        }
+        count
+    }
+
+    /// Función helper: renueva el TTL del almacenamiento de instancia (tareas e índices) según
+    /// la política vigente, para que los datos en uso no caduquen aunque nadie lo pida. La llaman
+    /// al principio las funciones públicas que tocan tareas, no los helpers de lectura/escritura
+    fn bump_ttl(env: &Env) {
+        let (threshold, extend_to) = Self::get_ttl_policy(env.clone());
+        let extend_to = extend_to.min(env.storage().max_ttl());
+        env.storage().instance().extend_ttl(threshold.min(extend_to), extend_to);
+    }
+
+    /// Función helper: añade un ID al índice de su estado; false si ya estaba
+    fn status_index_add(env: &Env, status: &TaskStatus, task_id: u64) -> bool {
+        let bucket = Self::status_bucket(task_id);
+        let key = DataKey::StatusBucket(status.clone(), bucket);
+        let mut ids: Vec<u64> = Self::load_ids(env, &key);
+
+        let pos = match ids.binary_search(task_id) {
+            Ok(_) => return false,
+            Err(pos) => pos,
+        };
+        if ids.is_empty() {
+            let list_key = DataKey::StatusBuckets(status.clone());
+            let mut buckets: Vec<u32> = env.storage().instance().get(&list_key).unwrap_or(Vec::new(env));
+            if let Err(i) = buckets.binary_search(bucket) {
+                buckets.insert(i, bucket);
+                env.storage().instance().set(&list_key, &buckets);
+            }
+        }
+        ids.insert(pos, task_id);
+        env.storage().instance().set(&key, &ids);
+        true
+    }
+
+    /// Función helper: cubo del índice por estado al que va un ID (en u32 caben 2^38 IDs)
+    fn status_bucket(task_id: u64) -> u32 {
+        (task_id / STATUS_BUCKET_SIZE) as u32
+    }
+
+    /// Función helper: quita un ID del índice de su estado (si estaba)
+    fn status_index_remove(env: &Env, status: &TaskStatus, task_id: u64) {
+        let bucket = Self::status_bucket(task_id);
+        let key = DataKey::StatusBucket(status.clone(), bucket);
+        let mut ids: Vec<u64> = Self::load_ids(env, &key);
+
+        if let Ok(pos) = ids.binary_search(task_id) {
+            ids.remove(pos);
+            if ids.is_empty() {
+                env.storage().instance().remove(&key);
+                let list_key = DataKey::StatusBuckets(status.clone());
+                let mut buckets: Vec<u32> = env.storage().instance().get(&list_key).unwrap_or(Vec::new(env));
+                if let Ok(i) = buckets.binary_search(bucket) {
+                    buckets.remove(i);
+                    env.storage().instance().set(&list_key, &buckets);
+                }
+            } else {
+                env.storage().instance().set(&key, &ids);
+            }
+        }
+    }
+
+    /// Función helper: tarea visible en los listados globales (no eliminada, programada ni oculta)
+    fn load_listable(env: &Env, task_id: u64) -> Option<Task> {
+        let task = Self::load_task(env, task_id)?;
+        if task.status == TaskStatus::Deleted
+            || task.status == TaskStatus::Scheduled
+            || task.status == TaskStatus::Backlog
+            || Self::is_hidden(env.clone(), task_id)
+        {
+            return None;
+        }
+        Some(task)
+    }
+
+    /// Función helper: valida el tamaño de una descripción cifrada
+    fn validate_ciphertext(ciphertext: &Bytes) -> Result<(), TaskError> {
+        if ciphertext.is_empty() || ciphertext.len() > MAX_CIPHERTEXT_LEN {
+            return Err(TaskError::InvalidTaskData);
+        }
+        Ok(())
+    }
+
+    /// Función helper: bytes UTF-8 de un String (como mucho MAX_DESCRIPTION_LEN)
+    fn string_to_bytes(env: &Env, text: &String) -> Result<Bytes, TaskError> {
+        let len = text.len() as usize;
+        if len == 0 || len > MAX_DESCRIPTION_LEN {
+            return Err(TaskError::InvalidTaskData);
+        }
+        let mut buf = [0u8; MAX_DESCRIPTION_LEN];
+        text.copy_into_slice(&mut buf[..len]);
+        Ok(Bytes::from_slice(env, &buf[..len]))
+    }
+
+    /// Función helper: validador común de descripciones (alta y modificación). Quita los espacios
+    /// en blanco de los extremos y exige MIN_DESCRIPTION_LEN..=MAX_DESCRIPTION_LEN con algún
+    /// carácter que no sea de control
+    fn normalize_description(env: &Env, description: &String) -> Result<String, TaskError> {
+        let len = description.len() as usize;
+        if len == 0 || len > MAX_DESCRIPTION_LEN {
+            return Err(TaskError::InvalidTaskData);
+        }
+        let mut buf = [0u8; MAX_DESCRIPTION_LEN];
+        description.copy_into_slice(&mut buf[..len]);
+
+        let text = core::str::from_utf8(&buf[..len]).map_err(|_| TaskError::InvalidTaskData)?;
+        let trimmed = text.trim();
+        if trimmed.chars().count() < MIN_DESCRIPTION_LEN || trimmed.chars().all(char::is_control) {
+            return Err(TaskError::InvalidTaskData);
+        }
+
+        if trimmed.len() == len {
+            return Ok(description.clone());
+        }
+        Ok(String::from_str(env, trimmed))
+    }
+
+    /// Función helper: guarda una acción en el timelock, ejecutable pasada la espera vigente
+    fn queue_action(env: &Env, action: AdminAction) -> Result<u32, TaskError> {
+        let action_id: u32 = env.storage().instance().get(&NEXT_ACTION_ID_KEY).unwrap_or(1);
+        let following = Self::checked_next(action_id)?;
+        let pending = PendingAction {
+            action,
+            eta: env.ledger().timestamp() + Self::get_action_delay(env.clone()),
+        };
+        env.storage().instance().set(&DataKey::PendingAction(action_id), &pending);
+        env.storage().instance().set(&NEXT_ACTION_ID_KEY, &following);
+
+        Self::emit_admin_event(env, symbol_short!("scheduled"), action_id);
+        Ok(action_id)
+    }
+
+    /// Función helper: ejecuta una reasignación de emergencia, moviendo la tarea entre los
+    /// índices de propietario y registrando el motivo
+    fn reassign_task(env: &Env, task_id: u64, new_owner: Address, reason: String) -> Result<(), TaskError> {
+        let mut task = Self::load_task(env, task_id).ok_or(TaskError::TaskNotFound)?;
+        let previous_owner = task.owner.clone();
+
+        let mut old_index: Vec<u64> = Self::load_ids(env, &previous_owner);
+        if let Some(i) = old_index.first_index_of(task_id) {
+            old_index.remove(i);
+            env.storage().instance().set(&previous_owner, &old_index);
+        }
+        let mut new_index: Vec<u64> = Self::load_ids(env, &new_owner);
+        new_index.push_back(task_id);
+        env.storage().instance().set(&new_owner, &new_index);
+
+        Self::move_open_counters(env, &task, &new_owner);
+        task.owner = new_owner.clone();
+        Self::save_task_unattended(env, &mut task);
+
+        let record = RecoveryRecord {
+            previous_owner: previous_owner.clone(),
+            new_owner,
+            reason,
+            executed_at: env.ledger().timestamp(),
+        };
+        env.storage().instance().set(&DataKey::Recovery(task_id), &record);
+
+        Self::emit_transfer_event(env, symbol_short!("reassign"), task_id, previous_owner, task.owner);
+        Ok(())
+    }
+
+    /// Función helper: cuenta una tarea completada por un referido y, al llegar a
+    /// REFERRAL_THRESHOLD, abona los puntos a su referidor
+    fn credit_referral(env: &Env, owner: &Address) {
+        let key = DataKey::Referral(owner.clone());
+        let mut referral: Referral = match env.storage().instance().get(&key) {
+            Some(referral) => referral,
+            None => return,
+        };
+        if referral.rewarded {
+            return;
+        }
+
+        referral.completed += 1;
+        if referral.completed >= REFERRAL_THRESHOLD {
+            referral.rewarded = true;
+            let points = Self::get_referral_points(env.clone(), referral.referrer.clone()) + REFERRAL_POINTS;
+            env.storage().instance().set(&DataKey::ReferralPoints(referral.referrer.clone()), &points);
+        }
+        env.storage().instance().set(&key, &referral);
+    }
+
+    /// Función helper: error si otra dirección tiene un bloqueo vigente sobre la tarea
+    fn ensure_not_locked(env: &Env, task_id: u64, caller: &Address) -> Result<(), TaskError> {
+        match Self::get_task_lock(env.clone(), task_id) {
+            Some(lock) if lock.holder != *caller => Err(TaskError::TaskLocked),
+            _ => Ok(()),
+        }
+    }
+
+    /// Función helper: publica un evento de tarea con el siguiente número de secuencia de eventos
+    /// (topics: ("task", acción); data: (task_id, event_seq))
+    fn emit_task_event(env: &Env, action: Symbol, task_id: u64) {
+        let event_seq = Self::next_event_seq(env);
+        env.events().publish((symbol_short!("task"), action), (task_id, event_seq));
+    }
+
+    /// Función helper: publica un cambio de estado de una tarea con el estado anterior y el nuevo,
+    /// para que los indexadores lleven la cuenta por estado sin releer el almacenamiento
+    fn emit_status_event(env: &Env, action: Symbol, task_id: u64, old_status: TaskStatus, new_status: TaskStatus) {
+        let event_seq = Self::next_event_seq(env);
+        env.events().publish((symbol_short!("task"), action), (task_id, event_seq, old_status, new_status));
+    }
+
+    /// Función helper: publica un cambio de propietario de una tarea con el anterior y el nuevo
+    fn emit_transfer_event(env: &Env, action: Symbol, task_id: u64, previous_owner: Address, new_owner: Address) {
+        let event_seq = Self::next_event_seq(env);
+        env.events().publish((symbol_short!("task"), action), (task_id, event_seq, previous_owner, new_owner));
+    }
+
+    /// Función helper: publica un evento del timelock de administración
+    /// (topics: ("admin", paso); data: (action_id, event_seq))
+    fn emit_admin_event(env: &Env, step: Symbol, action_id: u32) {
+        let event_seq = Self::next_event_seq(env);
+        env.events().publish((symbol_short!("admin"), step), (action_id, event_seq));
+    }
+
+    /// Función helper: consume el siguiente número de secuencia de eventos (compartido por todos)
+    fn next_event_seq(env: &Env) -> u32 {
+        let event_seq: u32 = env.storage().instance().get(&EVENT_SEQ_KEY).unwrap_or(0) + 1;
+        env.storage().instance().set(&EVENT_SEQ_KEY, &event_seq);
+        event_seq
+    }
+
+    /// Función helper: marca una tarea como completada y actualiza estadísticas, objetivo
+    /// vinculado y referidos (las comprobaciones de permisos ya están hechas)
+    fn complete_task(env: &Env, mut task: Task) -> Result<(), TaskError> {
+        let task_id = task.id;
+
+        if task.status == TaskStatus::Completed {
+             return Err(TaskError::TaskAlreadyCompleted);
+        }
+        if task.status == TaskStatus::Cancelled {
+            return Err(TaskError::TaskCancelled);
+        }
+        let old_status = Self::transition(&mut task, TaskStatus::Completed)?;
+
+        // Si la tarea depende de un oráculo, este debe confirmar la afirmación
+        if let Some(check) = env.storage().instance().get::<DataKey, OracleCheck>(&DataKey::TaskOracle(task_id)) {
+            if !TaskOracleClient::new(env, &check.oracle).verify(&task_id, &check.claim) {
+                return Err(TaskError::OracleRejected);
+            }
+        }
+
+        if Self::is_open(&old_status) {
+            Self::record_day_activity(env, &task.owner, false, true);
+            Self::track_due(env, &task, false);
+        }
+
+        Self::count_stat(env, |stats| stats.completed += 1);
+
+        Self::save_task(env, &mut task);
+        Self::emit_status_event(env, symbol_short!("completed"), task_id, old_status, TaskStatus::Completed);
+
+        // Avanzar el objetivo vinculado, si lo hay
+        if let Some(goal_id) = env.storage().instance().get::<DataKey, u32>(&DataKey::TaskGoal(task_id)) {
+            if let Some(mut goal) = env.storage().instance().get::<DataKey, Goal>(&DataKey::Goal(goal_id)) {
+                goal.progress += 1;
+                env.storage().instance().set(&DataKey::Goal(goal_id), &goal);
+            }
+        }
+
+        Self::credit_referral(env, &task.owner);
+        Ok(())
+    }
 
-// Estructura de una tarea, con 'owner' como Address
-#[contracttype]
-#[derive(Clone, Debug, Eq, PartialEq)]
-pub struct Task {
-    pub id: u32,
-    pub description: String,
-    pub owner: Address,
-    pub status: TaskStatus,
-    pub timestamp: u64,
-}
+    /// Función helper: registra en el cubo del día actual (UTC) la apertura de una tarea
+    /// ('opened') o su cierre, contando como completada si 'completed'
+    fn record_day_activity(env: &Env, owner: &Address, opened: bool, completed: bool) {
+        let (key, mut stats) = Self::adjust_open_count(env, owner, opened);
+        if opened {
+            stats.created += 1;
+        }
+        if completed {
+            stats.completed += 1;
+        }
+        env.storage().instance().set(&key, &stats);
+    }
 
-// Enum de errores personalizados
-#[contracterror]
-#[repr(u32)]
-#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
-pub enum TaskError {
-    TaskNotFound = 1,
-    InvalidTaskData = 2,
-    Unauthorized = 3,
-    TaskAlreadyCompleted = 4, // Usado también si se intenta modificar una tarea no-Pendiente
-}
+    /// Función helper: suma ('opened') o resta una tarea abierta del propietario y retorna su cubo
+    /// del día actual (UTC) con 'open_end' ya al día, para que el llamador lo complete y lo guarde
+    fn adjust_open_count(env: &Env, owner: &Address, opened: bool) -> (DataKey, DayStats) {
+        let open_key = DataKey::OpenCount(owner.clone());
+        let open: u32 = env.storage().instance().get(&open_key).unwrap_or(0);
+        let open = if opened { open + 1 } else { open.saturating_sub(1) };
+        env.storage().instance().set(&open_key, &open);
 
-// --- CONTRATO Y CONSTANTES ---
+        let today = env.ledger().timestamp() / DAY_SECS;
+        let key = DataKey::DayStats(owner.clone(), today);
+        let mut stats: DayStats = match env.storage().instance().get(&key) {
+            Some(stats) => stats,
+            None => {
+                // Primer movimiento del día: se anota para localizar el cubo en los informes
+                let days_key = DataKey::ActiveDays(owner.clone());
+                let mut days: Vec<u64> = env.storage().instance().get(&days_key).unwrap_or(Vec::new(env));
+                days.push_back(today);
+                env.storage().instance().set(&days_key, &days);
+                DayStats { created: 0, completed: 0, open_end: 0 }
+            }
+        };
+        stats.open_end = open;
+        (key, stats)
+    }
 
-#[contract]
-pub struct ToDoListContract;
+    /// Función helper: pasa una tarea abierta (aún con su propietario anterior) de los contadores
+    /// de tareas abiertas, vencimientos y cubos del día de este a los de 'new_owner', sin
+    /// contarla como creada ni concluida
+    fn move_open_counters(env: &Env, task: &Task, new_owner: &Address) {
+        if !Self::is_open(&task.status) {
+            return;
+        }
+        Self::track_due(env, task, false);
+        let mut moved = task.clone();
+        moved.owner = new_owner.clone();
+        Self::track_due(env, &moved, true);
 
-// Constante para la clave del próximo ID
-const NEXT_ID_KEY: Symbol = symbol_short!("next_id");
+        for (owner, opened) in [(&task.owner, false), (new_owner, true)] {
+            let (key, stats) = Self::adjust_open_count(env, owner, opened);
+            env.storage().instance().set(&key, &stats);
+        }
+    }
 
+    /// Función helper: suma ('add') o resta la tarea del cubo de vencimientos de su fecha límite
+    fn track_due(env: &Env, task: &Task, add: bool) {
+        if let Some(due) = task.due_date {
+            let key = DataKey::DueOpen(task.owner.clone(), due / DAY_SECS);
+            let count: u32 = env.storage().instance().get(&key).unwrap_or(0);
+            let count = if add { count + 1 } else { count.saturating_sub(1) };
+            env.storage().instance().set(&key, &count);
+        }
+    }
 
-// --- IMPLEMENTACIÓN DEL CONTRATO ---
+    /// Función helper: pasa una tarea a Deleted (papelera) y actualiza contadores y estadísticas
+    /// (las comprobaciones de permisos ya están hechas)
+    fn soft_delete(env: &Env, mut task: Task) -> Result<(), TaskError> {
+        let task_id = task.id;
+        let old_status = Self::transition(&mut task, TaskStatus::Deleted)?;
+        if Self::is_open(&old_status) {
+            Self::record_day_activity(env, &task.owner, false, false);
+            Self::track_due(env, &task, false);
+        }
 
-#[contractimpl]
-impl ToDoListContract {
+        Self::count_stat(env, |stats| stats.deleted += 1);
 
-    // 1. CREAR: Añadir nueva tarea y crear índice de propietario
-    pub fn add_task(env: Env, description: String, owner: Address) -> Result<u32, TaskError> {
-        // Validación de Seguridad: La dirección 'owner' debe firmar la transacción
-        owner.require_auth(); 
+        Self::save_task(env, &mut task);
+        env.storage().instance().set(&DataKey::DeletedAt(task_id), &env.ledger().timestamp());
+        Self::emit_status_event(env, symbol_short!("deleted"), task_id, old_status, TaskStatus::Deleted);
+        Ok(())
+    }
 
-        // Validar que la descripción no está vacía
-        if description.len() == 0 {
-            return Err(TaskError::InvalidTaskData);
+    /// Función helper: máquina de estados de las tareas. Aplica el cambio a 'to' si es legal desde
+    /// el estado actual y retorna el anterior. Completed, Cancelled y Deleted son finales
+    /// ('undo' restaura la versión anterior completa sin pasar por aquí).
+    fn transition(task: &mut Task, to: TaskStatus) -> Result<TaskStatus, TaskError> {
+        let legal = match task.status {
+            TaskStatus::Pending => matches!(to, TaskStatus::Completed | TaskStatus::Blocked | TaskStatus::WaitingOn | TaskStatus::Backlog | TaskStatus::Cancelled | TaskStatus::Deleted),
+            TaskStatus::Scheduled => matches!(to, TaskStatus::Pending | TaskStatus::Cancelled | TaskStatus::Deleted),
+            TaskStatus::Blocked => matches!(to, TaskStatus::Pending | TaskStatus::Cancelled | TaskStatus::Deleted),
+            TaskStatus::WaitingOn => matches!(to, TaskStatus::Pending | TaskStatus::Completed | TaskStatus::Cancelled | TaskStatus::Deleted),
+            TaskStatus::Backlog => matches!(to, TaskStatus::Pending | TaskStatus::Cancelled | TaskStatus::Deleted),
+            TaskStatus::Completed | TaskStatus::Cancelled | TaskStatus::Deleted => false,
+        };
+        if !legal {
+            return Err(TaskError::InvalidTransition);
         }
-        
-        // Obtener el próximo ID disponible
-        let next_id = Self::get_next_task_id(&env);
+        Ok(core::mem::replace(&mut task.status, to))
+    }
+
+    /// Función helper: indica si el estado cuenta como tarea abierta (aún por hacer)
+    fn is_open(status: &TaskStatus) -> bool {
+        matches!(status, TaskStatus::Pending | TaskStatus::Scheduled | TaskStatus::Blocked | TaskStatus::WaitingOn)
+    }
+
+    /// Función helper: día actual (desde epoch) en la zona horaria del propietario
+    fn local_day(env: &Env, owner: &Address) -> u64 {
+        let offset = Self::get_utc_offset(env.clone(), owner.clone()) as i64;
+        let local = env.ledger().timestamp() as i64 + offset;
+        local.max(0) as u64 / DAY_SECS
+    }
+
+    /// Función helper: lee unas estadísticas de concentración (cero si no existen)
+    fn read_focus(env: &Env, key: &DataKey) -> FocusStats {
+        env.storage().instance().get(key).unwrap_or(FocusStats {
+            sessions: 0,
+            focused_secs: 0,
+        })
+    }
+
+    /// Función helper: suma una sesión completada a unas estadísticas de concentración
+    fn add_focus(env: &Env, key: &DataKey, duration: u64) {
+        let mut stats = Self::read_focus(env, key);
+        stats.sessions += 1;
+        stats.focused_secs += duration;
+        env.storage().instance().set(key, &stats);
+    }
+
+    /// Función helper: indica si 'editor' tiene un permiso de edición vigente sobre la tarea
+    fn has_edit_grant(env: &Env, task_id: u64, editor: &Address) -> bool {
+        match env.storage().instance().get::<DataKey, u64>(&DataKey::EditGrant(task_id, editor.clone())) {
+            Some(expires_at) => env.ledger().timestamp() < expires_at,
+            None => false,
+        }
+    }
+
+    /// Función helper: niveles de prioridad que debe subir una tarea según su antigüedad
+    fn aging_steps(env: &Env, task: &Task, max_age: u64) -> u64 {
+        let anchor: u64 = env.storage().instance().get(&DataKey::AgedAt(task.id)).unwrap_or(task.timestamp);
+        env.ledger().timestamp().saturating_sub(anchor) / max_age
+    }
+
+    /// Función helper: sube 'steps' niveles de prioridad, sin pasar de Critical
+    fn raise_priority(priority: TaskPriority, steps: u64) -> TaskPriority {
+        let level = match priority {
+            TaskPriority::Low => 0,
+            TaskPriority::Medium => 1,
+            TaskPriority::High => 2,
+            TaskPriority::Critical => 3,
+        } + steps.min(3);
+
+        match level {
+            0 => TaskPriority::Low,
+            1 => TaskPriority::Medium,
+            2 => TaskPriority::High,
+            _ => TaskPriority::Critical,
+        }
+    }
+
+    /// Función helper: valida y crea una tarea con el estado inicial indicado, indexándola
+    /// por propietario. La firma de 'owner' la exige cada punto de entrada.
+    fn create_task(env: &Env, description: String, owner: Address, status: TaskStatus) -> Result<u64, TaskError> {
+        // Las cuentas congeladas no pueden crear tareas
+        Self::ensure_account_active(env, &owner)?;
+
+        // Validar la descripción y quitarle los espacios de los extremos
+        let description = Self::normalize_description(env, &description)?;
+
+        Self::insert_task(env, description, owner, status)
+    }
+
+    /// Función helper: guarda e indexa una tarea nueva, sin validaciones
+    fn insert_task(env: &Env, description: String, owner: Address, status: TaskStatus) -> Result<u64, TaskError> {
+        // Obtener el próximo ID disponible (sin desbordar el contador)
+        let next_id = Self::get_next_task_id(env);
+        let following_id = next_id.checked_add(1).ok_or(TaskError::CounterOverflow)?;
         
         // Timestamp del bloque en epoch UNIX
         let timestamp: u64 = env.ledger().timestamp();
 
-        let new_task = Task {
+        let mut new_task = Task {
             id: next_id,
             description: description.clone(),
             owner: owner.clone(),
-            status: TaskStatus::Pending,
-            timestamp: timestamp,
+            status,
+            timestamp,
+            priority: TaskPriority::Medium,
+            due_date: None,
+            updated_at: timestamp,
         };
 
+        // Un despliegue nuevo nace ya con el esquema actual
+        if next_id == 1 {
+            env.storage().instance().set(&STORAGE_VERSION_KEY, &STORAGE_VERSION);
+        }
+
         // 1. Guardar la tarea
-        env.storage().instance().set(&next_id, &new_task);
+        Self::save_task(env, &mut new_task);
         
-        // 2. Indexación de tareas por Propietario (Address -> Vec<u32>)
+        // 2. Indexación de tareas por Propietario (Address -> Vec<u64>)
         // La clave de almacenamiento es la Address del propietario
-        let mut owner_tasks: Vec<u32> = env.storage().instance().get(&owner).unwrap_or(Vec::new(&env));
+        let mut owner_tasks: Vec<u64> = Self::load_ids(env, &owner);
         owner_tasks.push_back(next_id);
         env.storage().instance().set(&owner, &owner_tasks);
+
+        // Numeración por propietario (owner, seq): 1..N para cada creador, además del ID global
+        let seq: u32 = env.storage().instance().get(&DataKey::OwnerNextSeq(owner.clone())).unwrap_or(1);
+        let following_seq = Self::checked_next(seq)?;
+        env.storage().instance().set(&DataKey::OwnerSeq(owner.clone(), seq), &next_id);
+        env.storage().instance().set(&DataKey::TaskSeq(next_id), &(owner.clone(), seq));
+        env.storage().instance().set(&DataKey::OwnerNextSeq(owner.clone()), &following_seq);
+
+        // Contabilizar la creación en el cubo del día (informe semanal)
+        Self::record_day_activity(env, &owner, true, false);
+        Self::count_stat(env, |stats| stats.created += 1);
         
         // 3. Actualizar el índice de IDs
-        env.storage().instance().set(&NEXT_ID_KEY, &(next_id + 1));
+        env.storage().instance().set(&NEXT_ID_KEY, &following_id);
+
+        Self::emit_task_event(env, symbol_short!("created"), next_id);
 
         Ok(next_id)
     }
 
-    // 2. LEER: Obtener tarea por ID
-    pub fn get_task_by_id(env: Env, task_id: u32) -> Option<Task> {
-        env.storage().instance().get(&task_id)
+    /// Función helper: tareas visibles de un propietario (ni eliminadas, ni programadas a futuro,
+    /// ni aparcadas en el backlog), en el orden de su índice
+    fn list_owner_tasks(env: &Env, owner: &Address) -> Vec<Task> {
+        let mut tasks = Vec::new(env);
+        for task_id in Self::load_ids(env, owner).iter() {
+            if let Some(task) = Self::load_task(env, task_id) {
+                if task.status != TaskStatus::Deleted && task.status != TaskStatus::Scheduled && task.status != TaskStatus::Backlog {
+                    tasks.push_back(task);
+                }
+            }
+        }
+        tasks
     }
 
-    // 3. LEER AVANZADO: Retorna todas las tareas (no eliminadas) de un propietario específico
-    // Esta función usa el índice que se creó en 'add_task'.
-    pub fn get_tasks_by_owner(env: Env, owner: Address) -> Vec<Task> {
-        let mut tasks = Vec::new(&env);
-        
-        // Intentar obtener la lista de IDs directamente desde la clave Address
-        if let Some(task_ids) = env.storage().instance().get::<Address, Vec<u32>>(&owner) {
-            
-            // Iterar sobre los IDs indexados
-            for task_id in task_ids.iter() {
-                if let Some(task) = Self::get_task_by_id(env.clone(), task_id) {
-                    // Solo incluir tareas que no estén marcadas como Deleted
-                    if task.status != TaskStatus::Deleted {
-                        tasks.push_back(task);
-                    }
+    /// Función helper: tareas pendientes de un propietario en un contexto. El índice no se
+    /// actualiza al transferir: solo cuentan las que siguen siendo suyas
+    fn list_context_tasks(env: &Env, owner: &Address, context: TaskContext) -> Vec<Task> {
+        let ids: Vec<u64> = Self::load_ids(env, &OrgKey::ContextTasks(owner.clone(), context));
+        let mut tasks = Vec::new(env);
+        for task_id in ids.iter() {
+            if let Some(task) = Self::load_task(env, task_id) {
+                if task.owner == *owner && task.status == TaskStatus::Pending {
+                    tasks.push_back(task);
                 }
             }
         }
         tasks
     }
-    
-    // 4. ACTUALIZAR: Concluir tarea
-    pub fn task_completed(env: Env, task_id: u32, caller: Address) -> Result<(), TaskError> {
-        caller.require_auth();
 
-        let mut task: Task = env
-            .storage()
-            .instance()
-            .get(&task_id)
-            .ok_or(TaskError::TaskNotFound)?;
+    /// Función helper: lee una tarea con el esquema actual. Un registro v1 o v2 se migra al leerlo,
+    /// junto con sus entradas auxiliares (las tareas programadas ya vencidas se leen como Pending)
+    fn load_task(env: &Env, task_id: u64) -> Option<Task> {
+        if Self::get_storage_version(env.clone()) < STORAGE_VERSION {
+            Self::migrate_one(env, task_id);
+        }
+        let task: Option<Task> = env.storage().instance().get(&DataKey::Task(task_id));
+        task.map(|task| Self::activate_if_due(env, task))
+    }
 
-        if task.owner != caller {
-            return Err(TaskError::Unauthorized);
+    /// Función helper: como 'load_task', pero con el estado tal como está guardado (sin activar
+    /// las tareas programadas ya vencidas)
+    fn load_stored_task(env: &Env, task_id: u64) -> Option<Task> {
+        if Self::get_storage_version(env.clone()) < STORAGE_VERSION {
+            Self::migrate_one(env, task_id);
         }
-        
-        if task.status == TaskStatus::Completed {
-             return Err(TaskError::TaskAlreadyCompleted);
+        env.storage().instance().get(&DataKey::Task(task_id))
+    }
+
+    /// Función helper: una tarea programada cuya hora ya llegó se trata como pendiente
+    fn activate_if_due(env: &Env, mut task: Task) -> Task {
+        if task.status == TaskStatus::Scheduled {
+            let scheduled_for: u64 = env.storage().instance().get(&DataKey::ScheduledFor(task.id)).unwrap_or(0);
+            if env.ledger().timestamp() >= scheduled_for {
+                task.status = TaskStatus::Pending;
+            }
         }
+        task
+    }
 
-        task.status = TaskStatus::Completed;
+    /// Función helper: guarda una tarea con el esquema actual y actualiza 'updated_at'
+    /// (la versión que se sobrescribe queda como instantánea para 'undo')
+    fn save_task(env: &Env, task: &mut Task) {
+        Self::write_task(env, task, true);
+    }
 
-        env.storage().instance().set(&task_id, &task);
-        Ok(())
+    /// Función helper: como 'save_task', pero sin tocar la instantánea para 'undo'. La usan los
+    /// keepers y el admin, para que sus cambios no sustituyan la última modificación del propietario.
+    fn save_task_unattended(env: &Env, task: &mut Task) {
+        Self::write_task(env, task, false);
     }
 
-    // 5. ACTUALIZAR: Modificar la descripción de una tarea pendiente (NUEVA FUNCIÓN)
-    pub fn update_task_description(env: Env, task_id: u32, caller: Address, new_description: String) -> Result<(), TaskError> {
-        caller.require_auth();
+    /// Función helper: escritura común de 'save_task' y 'save_task_unattended'
+    fn write_task(env: &Env, task: &mut Task, snapshot: bool) {
+        task.updated_at = env.ledger().timestamp();
 
-        let mut task: Task = env
-            .storage()
-            .instance()
-            .get(&task_id)
-            .ok_or(TaskError::TaskNotFound)?;
+        let previous = env.storage().instance().get::<DataKey, Task>(&DataKey::Task(task.id));
 
-        // Validación 1: Solo el propietario original
-        if task.owner != caller {
+        // Mantener el índice global por estado
+        match &previous {
+            Some(previous) if previous.status == task.status => {}
+            Some(previous) => {
+                Self::status_index_remove(env, &previous.status, task.id);
+                Self::status_index_add(env, &task.status, task.id);
+            }
+            None => {
+                Self::status_index_add(env, &task.status, task.id);
+            }
+        }
+
+        if let Some(previous) = previous.filter(|_| snapshot) {
+            let snapshot = UndoSnapshot {
+                task: previous,
+                saved_at: task.updated_at,
+            };
+            env.storage().instance().set(&DataKey::UndoSnapshot(task.id), &snapshot);
+        }
+        env.storage().instance().set(&DataKey::Task(task.id), task);
+    }
+
+    /// Función helper: descarta la instantánea para 'undo' de una tarea. La llaman las operaciones
+    /// que cambian entradas auxiliares que la instantánea no guarda, para que 'undo' no restaure un
+    /// registro en desacuerdo con ellas
+    fn discard_undo(env: &Env, task_id: u64) {
+        env.storage().instance().remove(&DataKey::UndoSnapshot(task_id));
+    }
+
+    /// Función helper: carga una tarea para modificarla. Exige la firma de 'caller' sobre 'args'
+    /// (el ID de la tarea y los valores nuevos, para que una autorización firmada para una tarea
+    /// o un valor no sirva para otro), que pueda actuar por el propietario y que ni la tarea ni
+    /// las cuentas implicadas estén congeladas.
+    fn load_task_for_update(env: &Env, task_id: u64, caller: &Address, args: Vec<Val>) -> Result<Task, TaskError> {
+        let task = Self::load_task(env, task_id).ok_or(TaskError::TaskNotFound)?;
+        Self::require_caller_auth(env, &task, caller, args);
+
+        if !Self::can_act_for(env, &task.owner, caller) {
             return Err(TaskError::Unauthorized);
         }
-        
-        // Validación 2: La nueva descripción no puede estar vacía
-        if new_description.len() == 0 {
-            return Err(TaskError::InvalidTaskData);
+
+        Self::ensure_not_frozen(env, task_id)?;
+        Self::ensure_not_locked(env, task_id, caller)?;
+        Self::ensure_account_active(env, caller)?;
+        Self::ensure_account_active(env, &task.owner)?;
+        Ok(task)
+    }
+
+    /// Función helper: como 'load_task_for_update', pero admite también a un editor delegado vigente
+    fn load_task_for_edit(env: &Env, task_id: u64, caller: &Address, args: Vec<Val>) -> Result<Task, TaskError> {
+        if !Self::has_edit_grant(env, task_id, caller) {
+            return Self::load_task_for_update(env, task_id, caller, args);
         }
-        
-        // Validación 3: Solo se pueden modificar tareas PENDIENTES
-        if task.status != TaskStatus::Pending {
-            return Err(TaskError::TaskAlreadyCompleted);
+
+        let task = Self::load_task(env, task_id).ok_or(TaskError::TaskNotFound)?;
+        Self::require_caller_auth(env, &task, caller, args);
+
+        Self::ensure_not_frozen(env, task_id)?;
+        Self::ensure_not_locked(env, task_id, caller)?;
+        Self::ensure_account_active(env, caller)?;
+        Self::ensure_account_active(env, &task.owner)?;
+        Ok(task)
+    }
+
+    /// Función helper: exige la firma de 'caller' sobre 'args'. Si no es el propietario (session
+    /// key o editor delegado), se añade a 'args' su nonce actual, que se consume: una intención
+    /// firmada por un delegado solo vale una vez.
+    fn require_caller_auth(env: &Env, task: &Task, caller: &Address, mut args: Vec<Val>) {
+        if *caller != task.owner {
+            let key = DataKey::Nonce(caller.clone());
+            let nonce: u64 = env.storage().instance().get(&key).unwrap_or(0);
+            args.push_back(nonce.into_val(env));
+            env.storage().instance().set(&key, &(nonce + 1));
         }
+        caller.require_auth_for_args(args);
+    }
 
-        task.description = new_description.clone();
+    /// Función helper: lee el registro guardado de una tarea sin migrarlo (v3, o v2/v1 mientras
+    /// dure la migración), para las comprobaciones que no deben escribir
+    fn peek_task(env: &Env, task_id: u64) -> Option<Task> {
+        if let Some(task) = env.storage().instance().get::<DataKey, Task>(&DataKey::Task(task_id)) {
+            return Some(task);
+        }
+        if Self::get_storage_version(env.clone()) >= STORAGE_VERSION {
+            return None;
+        }
+        let legacy_id = u32::try_from(task_id).ok()?;
+        match env.storage().instance().get::<LegacyKey, TaskV2>(&LegacyKey::Task(legacy_id)) {
+            Some(old) => Some(Task::from(old)),
+            None => env.storage().instance().get::<u32, TaskV1>(&legacy_id).map(Task::from),
+        }
+    }
 
-        env.storage().instance().set(&task_id, &task);
-        Ok(())
+    /// Función helper: migra el registro v2 (clave con ID u32) o v1 (el ID u32 como clave) de una
+    /// tarea, si existe, sin alterar sus marcas de tiempo
+    fn migrate_one(env: &Env, task_id: u64) -> bool {
+        let legacy_id = match u32::try_from(task_id) {
+            Ok(legacy_id) => legacy_id,
+            Err(_) => return false,
+        };
+
+        let task = if let Some(old) = env.storage().instance().get::<LegacyKey, TaskV2>(&LegacyKey::Task(legacy_id)) {
+            env.storage().instance().remove(&LegacyKey::Task(legacy_id));
+            Task::from(old)
+        } else if let Some(old) = env.storage().instance().get::<u32, TaskV1>(&legacy_id) {
+            env.storage().instance().remove(&legacy_id);
+            Task::from(old)
+        } else if env.storage().instance().has(&LegacyKey::Cold(legacy_id)) {
+            // Archivada en frío: solo quedan su resto y su número de secuencia
+            Self::move_entry(env, &LegacyKey::Cold(legacy_id), &DataKey::Cold(task_id));
+            Self::move_entry(env, &LegacyKey::TaskSeq(legacy_id), &DataKey::TaskSeq(task_id));
+            return true;
+        } else {
+            return false;
+        };
+
+        env.storage().instance().set(&DataKey::Task(task_id), &task);
+        Self::migrate_side_entries(env, legacy_id, &task);
+        true
     }
 
-    // 6. ACTUALIZAR (Soft Delete): Marcar tarea como eliminada
-    pub fn task_deleted(env: Env, task_id: u32, caller: Address) -> Result<(), TaskError> {
-        caller.require_auth();
+    /// Función helper: pasa a las claves v3 las entradas auxiliares de una tarea migrada y convierte
+    /// a u64 los índices compartidos en los que aparece. Se pierden la instantánea para deshacer
+    /// (guarda una tarea v2), los permisos de edición delegados y los bloqueos temporales, que no
+    /// se pueden enumerar por tarea.
+    fn migrate_side_entries(env: &Env, legacy_id: u32, task: &Task) {
+        let id = task.id;
 
-        let mut task: Task = env
-            .storage()
-            .instance()
-            .get(&task_id)
-            .ok_or(TaskError::TaskNotFound)?;
+        // Valores sin IDs de tarea: se copian tal cual
+        let data_keys = [
+            (LegacyKey::FrozenTask(legacy_id), DataKey::FrozenTask(id)),
+            (LegacyKey::ScheduledFor(legacy_id), DataKey::ScheduledFor(id)),
+            (LegacyKey::AgedAt(legacy_id), DataKey::AgedAt(id)),
+            (LegacyKey::BlockedReason(legacy_id), DataKey::BlockedReason(id)),
+            (LegacyKey::ActivePomodoro(legacy_id), DataKey::ActivePomodoro(id)),
+            (LegacyKey::TaskFocus(legacy_id), DataKey::TaskFocus(id)),
+            (LegacyKey::Habit(legacy_id), DataKey::Habit(id)),
+            (LegacyKey::TaskGoal(legacy_id), DataKey::TaskGoal(id)),
+            (LegacyKey::DeletedAt(legacy_id), DataKey::DeletedAt(id)),
+            (LegacyKey::Recovery(legacy_id), DataKey::Recovery(id)),
+            (LegacyKey::Hidden(legacy_id), DataKey::Hidden(id)),
+            (LegacyKey::DescriptionHash(legacy_id), DataKey::DescriptionHash(id)),
+            (LegacyKey::EncryptedDescription(legacy_id), DataKey::EncryptedDescription(id)),
+            (LegacyKey::CompletionCommitment(legacy_id), DataKey::CompletionCommitment(id)),
+            (LegacyKey::CompletionProof(legacy_id), DataKey::CompletionProof(id)),
+            (LegacyKey::TaskOracle(legacy_id), DataKey::TaskOracle(id)),
+            (LegacyKey::TaskSeq(legacy_id), DataKey::TaskSeq(id)),
+            (LegacyKey::Cold(legacy_id), DataKey::Cold(id)),
+        ];
+        for (old, new) in data_keys {
+            Self::move_entry(env, &old, &new);
+        }
+        let org_keys = [
+            (LegacyKey::TaskLang(legacy_id), OrgKey::TaskLang(id)),
+            (LegacyKey::TaskColor(legacy_id), OrgKey::TaskColor(id)),
+            (LegacyKey::TaskIcon(legacy_id), OrgKey::TaskIcon(id)),
+            (LegacyKey::TaskLocation(legacy_id), OrgKey::TaskLocation(id)),
+            (LegacyKey::TaskContext(legacy_id), OrgKey::TaskContext(id)),
+            (LegacyKey::TaskEffort(legacy_id), OrgKey::TaskEffort(id)),
+            (LegacyKey::WaitingOn(legacy_id), OrgKey::WaitingOn(id)),
+            (LegacyKey::Review(legacy_id), OrgKey::Review(id)),
+        ];
+        for (old, new) in org_keys {
+            Self::move_entry(env, &old, &new);
+        }
 
-        if task.owner != caller {
-            return Err(TaskError::Unauthorized);
+        // IDs sueltos y listas de IDs: se convierten a u64
+        for (old, new) in [
+            (LegacyKey::ParentTask(legacy_id), OrgKey::ParentTask(id)),
+            (LegacyKey::MergedInto(legacy_id), OrgKey::MergedInto(id)),
+        ] {
+            if let Some(other) = env.storage().instance().get::<LegacyKey, u32>(&old) {
+                env.storage().instance().set(&new, &u64::from(other));
+                env.storage().instance().remove(&old);
+            }
+        }
+        for (old, new) in [
+            (LegacyKey::Dependencies(legacy_id), OrgKey::Dependencies(id)),
+            (LegacyKey::Dependents(legacy_id), OrgKey::Dependents(id)),
+            (LegacyKey::DependencyRequests(legacy_id), OrgKey::DependencyRequests(id)),
+            (LegacyKey::Subtasks(legacy_id), OrgKey::Subtasks(id)),
+            (LegacyKey::MergedFrom(legacy_id), OrgKey::MergedFrom(id)),
+        ] {
+            if let Some(values) = env.storage().instance().get::<LegacyKey, Vec<Val>>(&old) {
+                env.storage().instance().set(&new, &Self::ids_from_vals(env, &values));
+                env.storage().instance().remove(&old);
+            }
+        }
+        let links_key = LegacyKey::TaskLinks(legacy_id);
+        if let Some(old_links) = env.storage().instance().get::<LegacyKey, Vec<TaskLinkV2>>(&links_key) {
+            let mut links = Vec::new(env);
+            for link in old_links.iter() {
+                links.push_back(TaskLink { task_id: u64::from(link.task_id), relation: link.relation, outgoing: link.outgoing });
+            }
+            env.storage().instance().set(&OrgKey::TaskLinks(id), &links);
+            env.storage().instance().remove(&links_key);
+        }
+        env.storage().instance().remove(&LegacyKey::UndoSnapshot(legacy_id));
+
+        // Índices compartidos en los que aparece
+        Self::convert_ids(env, &task.owner);
+        Self::convert_ids(env, &DataKey::StatusBucket(task.status.clone(), Self::status_bucket(id)));
+        if task.status == TaskStatus::Scheduled {
+            Self::convert_ids(env, &SCHEDULED_KEY);
+        }
+        if let Some((creator, seq)) = Self::get_task_seq(env.clone(), id) {
+            let key = DataKey::OwnerSeq(creator, seq);
+            if let Some(seq_id) = Self::load_id(env, &key) {
+                env.storage().instance().set(&key, &seq_id);
+            }
+        }
+        if let Some(context) = Self::get_task_context(env.clone(), id) {
+            Self::convert_ids(env, &OrgKey::ContextTasks(task.owner.clone(), context));
+        }
+        if let Some(effort) = Self::get_task_effort(env.clone(), id) {
+            Self::convert_ids(env, &OrgKey::EnergyTasks(task.owner.clone(), effort.energy));
+        }
+        if env.storage().instance().has(&OrgKey::Review(id)) {
+            Self::convert_ids(env, &OrgKey::ReviewTasks(task.owner.clone()));
+        }
+        if let Some((lat, lon)) = Self::get_task_location(env.clone(), id) {
+            Self::convert_ids(env, &OrgKey::GeoCell(lat.div_euclid(GEO_CELL), lon.div_euclid(GEO_CELL)));
         }
+    }
 
-        task.status = TaskStatus::Deleted;
+    /// Función helper: hash del registro v2 de una tarea, con el que se archivó antes del esquema v3
+    fn legacy_hash(env: &Env, task: &Task) -> Option<BytesN<32>> {
+        let legacy = TaskV2 {
+            id: u32::try_from(task.id).ok()?,
+            description: task.description.clone(),
+            owner: task.owner.clone(),
+            status: task.status.clone(),
+            timestamp: task.timestamp,
+            priority: task.priority.clone(),
+            due_date: task.due_date,
+            updated_at: task.updated_at,
+        };
+        Some(env.crypto().sha256(&legacy.to_xdr(env)).into())
+    }
 
-        env.storage().instance().set(&task_id, &task);
-        Ok(())
+    /// Función helper: mueve un valor de una clave a otra (si existe), sin interpretarlo
+    fn move_entry<K: IntoVal<Env, Val>>(env: &Env, old: &LegacyKey, new: &K) {
+        if let Some(value) = env.storage().instance().get::<LegacyKey, Val>(old) {
+            env.storage().instance().set(new, &value);
+            env.storage().instance().remove(old);
+        }
     }
 
-    // 7. FUNCIÓN AVANZADA: Transferir Propiedad
-    pub fn transfer_ownership(env: Env, task_id: u32, caller: Address, new_owner: Address) -> Result<(), TaskError> {
-        // NOTA: Esta implementación NO actualiza los índices de propietario. 
-        // Para tareas transferibles, un índice más complejo sería ideal.
-        caller.require_auth();
+    /// Función helper: lee una lista de IDs de tarea. Hasta terminar la migración al esquema v3
+    /// puede contener IDs u32, que se convierten al vuelo.
+    fn load_ids<K: IntoVal<Env, Val>>(env: &Env, key: &K) -> Vec<u64> {
+        if Self::get_storage_version(env.clone()) >= STORAGE_VERSION {
+            return env.storage().instance().get(key).unwrap_or(Vec::new(env));
+        }
+        match env.storage().instance().get::<K, Vec<Val>>(key) {
+            Some(values) => Self::ids_from_vals(env, &values),
+            None => Vec::new(env),
+        }
+    }
 
-        let mut task: Task = env
-            .storage()
-            .instance()
-            .get(&task_id)
-            .ok_or(TaskError::TaskNotFound)?;
+    /// Función helper: lee un ID de tarea guardado como u64 o, en esquemas anteriores, como u32
+    fn load_id<K: IntoVal<Env, Val>>(env: &Env, key: &K) -> Option<u64> {
+        let value = env.storage().instance().get::<K, Val>(key)?;
+        Self::id_from_val(env, &value)
+    }
 
-        if task.owner != caller {
-            return Err(TaskError::Unauthorized);
+    /// Función helper: reescribe con IDs u64 una lista de IDs de tarea (si existe)
+    fn convert_ids<K: IntoVal<Env, Val>>(env: &Env, key: &K) {
+        if let Some(values) = env.storage().instance().get::<K, Vec<Val>>(key) {
+            env.storage().instance().set(key, &Self::ids_from_vals(env, &values));
+        }
+    }
+
+    /// Función helper: IDs de tarea de una lista con elementos u32 o u64
+    fn ids_from_vals(env: &Env, values: &Vec<Val>) -> Vec<u64> {
+        let mut ids = Vec::new(env);
+        for value in values.iter() {
+            if let Some(id) = Self::id_from_val(env, &value) {
+                ids.push_back(id);
+            }
         }
+        ids
+    }
 
-        task.owner = new_owner.clone();
-        
-        env.storage().instance().set(&task_id, &task);
-        Ok(())
+    /// Función helper: ID de tarea guardado como u64 o como u32
+    fn id_from_val(env: &Env, value: &Val) -> Option<u64> {
+        match u64::try_from_val(env, value) {
+            Ok(id) => Some(id),
+            Err(_) => u32::try_from_val(env, value).ok().map(u64::from),
+        }
     }
 
-    // 8. LEER AVANZADO: Retorna todas las tareas pendientes y concluidas (excluye eliminadas)
-    // NOTA: Esta función itera sobre todos los IDs, no es eficiente para contratos con muchos datos.
-    pub fn get_all(env: Env) -> Vec<Task> {
-        let mut tasks = Vec::new(&env);
-        let last_id = Self::get_next_task_id(&env);
+    /// Función helper: hojas del árbol Merkle, rellenadas hasta una potencia de 2
+    fn merkle_leaves(env: &Env) -> Vec<BytesN<32>> {
+        let mut leaves = Vec::new(env);
+        let zero = BytesN::from_array(env, &[0u8; 32]);
 
-        for id in 1..last_id {
-            if let Some(task) = Self::get_task_by_id(env.clone(), id) {
-                if task.status != TaskStatus::Deleted {
-                    tasks.push_back(task);
-                }
+        for id in 1..Self::get_next_task_id(env) {
+            leaves.push_back(Self::merkle_leaf(env, id));
+        }
+
+        let mut size: u32 = 1;
+        while size < leaves.len() {
+            size *= 2;
+        }
+        while leaves.len() < size {
+            leaves.push_back(zero.clone());
+        }
+        leaves
+    }
+
+    /// Función helper: hoja Merkle de un ID (32 bytes a cero si no hay tarea)
+    fn merkle_leaf(env: &Env, id: u64) -> BytesN<32> {
+        match Self::load_task(env, id) {
+            Some(task) => {
+                let mut data = Bytes::from_array(env, &[0u8]);
+                data.append(&task.to_xdr(env));
+                env.crypto().sha256(&data).into()
             }
+            None => BytesN::from_array(env, &[0u8; 32]),
         }
-        tasks
     }
 
+    /// Función helper: nodo Merkle SHA-256(0x01 || izq || der)
+    fn merkle_node(env: &Env, left: &BytesN<32>, right: &BytesN<32>) -> BytesN<32> {
+        let mut data = Bytes::from_array(env, &[1u8]);
+        data.append(&Bytes::from(left));
+        data.append(&Bytes::from(right));
+        env.crypto().sha256(&data).into()
+    }
+
+    /// Función helper: añade la hoja número 'count' (desde 0) al acumulador. En el nivel k queda
+    /// el subárbol izquierdo pendiente de pareja si el bit k de 'count' está a 1 (si no, ceros).
+    fn merkle_push(env: &Env, frontier: &mut Vec<BytesN<32>>, count: u64, leaf: BytesN<32>) {
+        let zero = BytesN::from_array(env, &[0u8; 32]);
+        let mut node = leaf;
+        let mut level: u32 = 0;
+
+        while count >> level & 1 == 1 {
+            node = Self::merkle_node(env, &frontier.get(level).unwrap(), &node);
+            frontier.set(level, zero.clone());
+            level += 1;
+        }
+        if level == frontier.len() {
+            frontier.push_back(node);
+        } else {
+            frontier.set(level, node);
+        }
+    }
+
+    /// Función helper: raíz de 'count' hojas acumuladas, rellenando con ceros hasta una potencia
+    /// de 2 como 'merkle_leaves'
+    fn merkle_finish(env: &Env, frontier: &Vec<BytesN<32>>, count: u64) -> BytesN<32> {
+        if count == 0 {
+            return BytesN::from_array(env, &[0u8; 32]);
+        }
+        let depth = 64 - (count - 1).leading_zeros();
+        if count == 1 << depth {
+            // Árbol completo: ya está entero en el nivel más alto del acumulador
+            return frontier.get(depth).unwrap();
+        }
+
+        // Subárboles de ceros de cada nivel, para completar los huecos de la derecha
+        let mut zero = BytesN::from_array(env, &[0u8; 32]);
+        let mut node: Option<BytesN<32>> = None;
+        for level in 0..depth {
+            node = match (count >> level & 1 == 1, node) {
+                (true, Some(right)) => Some(Self::merkle_node(env, &frontier.get(level).unwrap(), &right)),
+                (true, None) => Some(Self::merkle_node(env, &frontier.get(level).unwrap(), &zero)),
+                (false, Some(left)) => Some(Self::merkle_node(env, &left, &zero)),
+                (false, None) => None,
+            };
+            zero = Self::merkle_node(env, &zero, &zero);
+        }
+        node.unwrap()
+    }
+
+    /// Función helper: calcula el siguiente nivel del árbol Merkle
+    fn merkle_next_level(env: &Env, level: &Vec<BytesN<32>>) -> Vec<BytesN<32>> {
+        let mut next = Vec::new(env);
+        let mut i = 0;
+
+        while i < level.len() {
+            next.push_back(Self::merkle_node(env, &level.get(i).unwrap(), &level.get(i + 1).unwrap()));
+            i += 2;
+        }
+        next
+    }
+
+    /// Función helper: exige la firma del admin configurado
+    fn require_admin(env: &Env) -> Result<Address, TaskError> {
+        let admin = Self::get_admin(env.clone()).ok_or(TaskError::Unauthorized)?;
+        admin.require_auth();
+        Ok(admin)
+    }
+
+    /// Función helper: error si la cuenta está congelada
+    fn ensure_account_active(env: &Env, addr: &Address) -> Result<(), TaskError> {
+        if env.storage().instance().has(&DataKey::FrozenAccount(addr.clone())) {
+            return Err(TaskError::AccountFrozen);
+        }
+        Ok(())
+    }
+
+    /// Función helper: error si la tarea está congelada
+    fn ensure_not_frozen(env: &Env, task_id: u64) -> Result<(), TaskError> {
+        if env.storage().instance().has(&DataKey::FrozenTask(task_id)) {
+            return Err(TaskError::TaskFrozen);
+        }
+        Ok(())
+    }
+
+    /// Función helper: indica si 'addr' es el admin configurado
+    fn is_admin(env: &Env, addr: &Address) -> bool {
+        Self::get_admin(env.clone()).as_ref() == Some(addr)
+    }
+
+    /// Función helper: indica si 'addr' es el admin o tiene asignado 'role'
+    fn is_admin_or(env: &Env, role: Role, addr: &Address) -> bool {
+        Self::is_admin(env, addr) || Self::has_role(env.clone(), role, addr.clone())
+    }
+
+    /// Función helper: indica si 'caller' puede actuar en nombre de 'owner'
+    /// (es el propio propietario o su session key todavía vigente)
+    fn can_act_for(env: &Env, owner: &Address, caller: &Address) -> bool {
+        if owner == caller {
+            return true;
+        }
+        match env.storage().instance().get::<DataKey, SessionKey>(&DataKey::SessionKey(owner.clone())) {
+            Some(session) => session.key == *caller && env.ledger().sequence() <= session.expires_at,
+            None => false,
+        }
+    }
+
+    /// Función helper para obtener el próximo ID disponible (u32 en los esquemas anteriores)
+    fn get_next_task_id(env: &Env) -> u64 {
+        Self::load_id(env, &NEXT_ID_KEY).unwrap_or(1)
+    }
 
-    /// Función helper para obtener el próximo ID disponible
-    fn get_next_task_id(env: &Env) -> u32 {
-        env.storage().instance().get(&NEXT_ID_KEY).unwrap_or(1)
+    /// Función helper: siguiente valor de un contador de IDs, o CounterOverflow si ya está en u32::MAX
+    fn checked_next(id: u32) -> Result<u32, TaskError> {
+        id.checked_add(1).ok_or(TaskError::CounterOverflow)
     }
 }
 
-// --- MÓDULO DE TESTS UNITARIOS ---
+// --- INTERFAZ PARA OTROS CONTRATOS ---
+
+pub mod interface;
+
+// --- UTILIDADES PARA TESTS ---
 
-// Si usas este archivo como 'lib.rs', debes crear un archivo 'test.rs'
-// o descomentar y completar este módulo para incluir todos los tests.
+#[cfg(any(test, feature = "testutils"))]
+pub mod testutils;
+
+#[cfg(all(test, feature = "bench"))]
+mod bench;
+
+// --- MÓDULO DE TESTS UNITARIOS ---
 
-/*
 #[cfg(test)]
-mod test {
-    use super::*; // Importar todo lo del scope superior
-    // ... (Colocar aquí todos los tests que hemos generado) ...
-}
-*/
\ No newline at end of file
+mod test;
@@ -1,13 +1,13 @@
-#[cfg(test)]
-mod test;
+extern crate std;
+
 // --- Requerido para simular firmas de direcciones ---
 use soroban_sdk::{
-    testutils::{Address as _, Ledger},
-    Env, String, Address,
+    testutils::{storage::Instance as _, Address as _, AuthorizedFunction, AuthorizedInvocation, Events, Ledger},
+    Env, IntoVal, String, Symbol, Address,
 };
 
 // Importar el contrato y las estructuras
-use crate::{ToDoListContract, ToDoListContractClient, Task, TaskStatus, TaskError, symbol_short};
+use crate::{ToDoListContract, ToDoListContractClient, AdminAction, DataKey, LegacyKey, TaskV2, EnergyLevel, LinkType, Role, SplitMode, StatsCounters, TaskContext, TaskLink, TaskV1, TaskStatus, TaskPriority, TaskError, symbol_short};
 
 
 // Función de configuración común para los tests
@@ -15,13 +15,17 @@ fn setup_env() -> (Env, ToDoListContractClient<'static>, Address, Address) {
     let env = Env::default();
     // Aumentar el tiempo del ledger para el timestamp de la tarea
     env.ledger().set_timestamp(1678886400); // 15 de marzo de 2023, 00:00:00 UTC
+    // Simular las firmas de todas las direcciones (se comprueban con 'env.auths()')
+    env.mock_all_auths();
     
-    let contract_id = env.register_contract(None, ToDoListContract);
+    // El administrador se fija al desplegar
+    let admin = Address::generate(&env);
+    let contract_id = env.register(ToDoListContract, (&admin,));
     let client = ToDoListContractClient::new(&env, &contract_id);
     
     // Crear direcciones simuladas para el propietario y otro usuario
-    let owner_a = Address::random(&env);
-    let owner_b = Address::random(&env);
+    let owner_a = Address::generate(&env);
+    let owner_b = Address::generate(&env);
 
     (env, client, owner_a, owner_b)
 }
@@ -52,7 +56,7 @@ fn test_add_task_success() {
 
     // Verificar que el próximo ID se incrementó
     let next_id_key = symbol_short!("next_id");
-    let next_id: u32 = env.storage().instance().get(&next_id_key).unwrap();
+    let next_id: u64 = env.as_contract(&client.address, || env.storage().instance().get(&next_id_key).unwrap());
     assert_eq!(next_id, 2);
 }
 
@@ -196,7 +200,7 @@ fn test_transfer_ownership_success() {
 #[test]
 fn test_transfer_ownership_unauthorized_fails() {
     let (env, client, owner_a, other_user) = setup_env();
-    let new_owner = Address::random(&env);
+    let new_owner = Address::generate(&env);
     let desc = String::from_str(&env, "Intento de transferencia no autorizada");
     let task_id = client.add_task(&desc, &owner_a);
 
@@ -242,8 +246,8 @@ fn test_get_all_filters_deleted() {
     assert!(!t3_present);
 
     // Verificar que las tareas restantes son las correctas
-    let task_ids: Vec<u32> = all_tasks.iter().map(|t| t.id).collect();
-    assert_eq!(task_ids, vec![1, 2, 4]);
+    let task_ids: std::vec::Vec<u64> = all_tasks.iter().map(|t| t.id).collect();
+    assert_eq!(task_ids, std::vec![1, 2, 4]);
 }
 
 #[test]
@@ -254,4 +258,2485 @@ fn test_get_all_empty() {
     
     // Debe retornar un Vec vacío
     assert!(all_tasks.is_empty());
-}
\ No newline at end of file
+}
+// =======================================================
+// TEST: session keys
+// =======================================================
+
+#[test]
+fn test_session_key_can_complete_owner_task() {
+    let (env, client, owner_a, session) = setup_env();
+    let task_id = client.add_task(&String::from_str(&env, "Editar desde el móvil"), &owner_a);
+
+    // 'owner_a' registra una session key válida hasta el ledger 100
+    client.set_session_key(&owner_a, &session, &100);
+
+    // La session key completa la tarea en nombre del propietario
+    client.task_completed(&task_id, &session);
+    let task = client.get_task_by_id(&task_id).unwrap();
+    assert_eq!(task.status, TaskStatus::Completed);
+}
+
+#[test]
+fn test_session_key_expired_fails() {
+    let (env, client, owner_a, session) = setup_env();
+    let task_id = client.add_task(&String::from_str(&env, "Sesión caducada"), &owner_a);
+
+    client.set_session_key(&owner_a, &session, &100);
+
+    // Avanzar el ledger más allá de la expiración
+    env.ledger().set_sequence_number(101);
+
+    let result = client.try_task_completed(&task_id, &session);
+    assert_eq!(result.err().unwrap().unwrap(), TaskError::Unauthorized);
+}
+
+#[test]
+fn test_session_key_revoked_and_cannot_transfer() {
+    let (env, client, owner_a, session) = setup_env();
+    let task_id = client.add_task(&String::from_str(&env, "Sin transferencias"), &owner_a);
+
+    client.set_session_key(&owner_a, &session, &100);
+
+    // Una session key no puede transferir la propiedad
+    let result = client.try_transfer_ownership(&task_id, &session, &session);
+    assert_eq!(result.err().unwrap().unwrap(), TaskError::Unauthorized);
+
+    // Tras revocarla, tampoco puede completar tareas
+    client.revoke_session_key(&owner_a);
+    assert!(client.get_session_key(&owner_a).is_none());
+    let result = client.try_task_completed(&task_id, &session);
+    assert_eq!(result.err().unwrap().unwrap(), TaskError::Unauthorized);
+}
+
+#[test]
+fn test_set_session_key_invalid_fails() {
+    let (env, client, owner_a, session) = setup_env();
+    env.ledger().set_sequence_number(50);
+
+    // Expiración en el pasado
+    let result = client.try_set_session_key(&owner_a, &session, &50);
+    assert_eq!(result.err().unwrap().unwrap(), TaskError::InvalidSessionKey);
+
+    // El propietario no puede ser su propia session key
+    let result = client.try_set_session_key(&owner_a, &owner_a, &100);
+    assert_eq!(result.err().unwrap().unwrap(), TaskError::InvalidSessionKey);
+}
+
+// =======================================================
+// TEST: freeze_task / unfreeze_task
+// =======================================================
+
+#[test]
+fn test_freeze_task_blocks_mutations() {
+    let (env, client, owner_a, new_owner) = setup_env();
+    let task_id = client.add_task(&String::from_str(&env, "Tarea en disputa"), &owner_a);
+
+    client.freeze_task(&task_id, &owner_a);
+    assert!(client.is_task_frozen(&task_id));
+
+    // Completar, editar y transferir fallan con TaskFrozen
+    let result = client.try_task_completed(&task_id, &owner_a);
+    assert_eq!(result.err().unwrap().unwrap(), TaskError::TaskFrozen);
+    let result = client.try_update_task_description(&task_id, &owner_a, &String::from_str(&env, "Nueva"));
+    assert_eq!(result.err().unwrap().unwrap(), TaskError::TaskFrozen);
+    let result = client.try_transfer_ownership(&task_id, &owner_a, &new_owner);
+    assert_eq!(result.err().unwrap().unwrap(), TaskError::TaskFrozen);
+
+    // Tras descongelar, se puede completar
+    client.unfreeze_task(&task_id, &owner_a);
+    client.task_completed(&task_id, &owner_a);
+    assert_eq!(client.get_task_by_id(&task_id).unwrap().status, TaskStatus::Completed);
+}
+
+#[test]
+fn test_admin_is_set_at_deploy() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let admin = Address::generate(&env);
+    let contract_id = env.register(ToDoListContract, (&admin,));
+    let client = ToDoListContractClient::new(&env, &contract_id);
+    assert_eq!(client.get_admin(), Some(admin.clone()));
+
+    // Cederlo es una acción programada: no cambia hasta que pasa el timelock
+    let new_admin = Address::generate(&env);
+    let action_id = client.schedule_action(&AdminAction::SetAdmin(new_admin.clone()));
+    assert_eq!(env.auths()[0].0, admin);
+    assert_eq!(client.get_admin(), Some(admin));
+
+    env.ledger().set_timestamp(env.ledger().timestamp() + 2 * 24 * 60 * 60);
+    client.execute_action(&action_id);
+    assert_eq!(client.get_admin(), Some(new_admin));
+}
+
+#[test]
+fn test_admin_freeze_cannot_be_lifted_by_owner() {
+    let (env, client, owner_a, _) = setup_env();
+    let admin = client.get_admin().unwrap();
+    let task_id = client.add_task(&String::from_str(&env, "Revisión de cumplimiento"), &owner_a);
+
+    client.freeze_task(&task_id, &admin);
+
+    // El propietario no puede descongelar ni volver a congelar
+    let result = client.try_unfreeze_task(&task_id, &owner_a);
+    assert_eq!(result.err().unwrap().unwrap(), TaskError::Unauthorized);
+    let result = client.try_freeze_task(&task_id, &owner_a);
+    assert_eq!(result.err().unwrap().unwrap(), TaskError::TaskFrozen);
+
+    client.unfreeze_task(&task_id, &admin);
+    assert!(!client.is_task_frozen(&task_id));
+}
+
+#[test]
+fn test_freeze_task_unauthorized_fails() {
+    let (env, client, owner_a, other_user) = setup_env();
+    let task_id = client.add_task(&String::from_str(&env, "Ajena"), &owner_a);
+
+    let result = client.try_freeze_task(&task_id, &other_user);
+    assert_eq!(result.err().unwrap().unwrap(), TaskError::Unauthorized);
+}
+
+// =======================================================
+// TEST: freeze_account / unfreeze_account
+// =======================================================
+
+#[test]
+fn test_freeze_account_blocks_create_and_mutate() {
+    let (env, client, owner_a, _) = setup_env();
+    let task_id = client.add_task(&String::from_str(&env, "Antes del bloqueo"), &owner_a);
+
+    client.freeze_account(&owner_a);
+    assert!(client.is_account_frozen(&owner_a));
+
+    // No puede crear ni completar tareas
+    let result = client.try_add_task(&String::from_str(&env, "Bloqueada"), &owner_a);
+    assert_eq!(result.err().unwrap().unwrap(), TaskError::AccountFrozen);
+    let result = client.try_task_completed(&task_id, &owner_a);
+    assert_eq!(result.err().unwrap().unwrap(), TaskError::AccountFrozen);
+
+    // Sus datos siguen siendo legibles
+    assert_eq!(client.get_tasks_by_owner(&owner_a).len(), 1);
+
+    client.unfreeze_account(&owner_a);
+    client.task_completed(&task_id, &owner_a);
+}
+
+#[test]
+fn test_freeze_account_without_admin_signature_fails() {
+    let (env, client, owner_a, _) = setup_env();
+
+    // Sin la firma del admin no se puede congelar ninguna cuenta
+    env.set_auths(&[]);
+    assert!(client.try_freeze_account(&owner_a).is_err());
+}
+
+// =======================================================
+// TEST: transfer_all
+// =======================================================
+
+#[test]
+fn test_transfer_all_in_batches() {
+    let (env, client, owner_a, new_wallet) = setup_env();
+    client.add_task(&String::from_str(&env, "T1"), &owner_a);
+    client.add_task(&String::from_str(&env, "T2"), &owner_a);
+    let t3_id = client.add_task(&String::from_str(&env, "T3"), &owner_a);
+    client.task_deleted(&t3_id, &owner_a);
+
+    // Primer lote: solo una tarea
+    assert_eq!(client.transfer_all(&owner_a, &new_wallet, &1), 1);
+    // Segundo lote: la tarea viva restante (la eliminada no se mueve)
+    assert_eq!(client.transfer_all(&owner_a, &new_wallet, &10), 1);
+    assert_eq!(client.transfer_all(&owner_a, &new_wallet, &10), 0);
+
+    // Ambos índices quedan reescritos
+    assert_eq!(client.get_tasks_by_owner(&owner_a).len(), 0);
+    let moved = client.get_tasks_by_owner(&new_wallet);
+    assert_eq!(moved.len(), 2);
+    assert!(moved.iter().all(|t| t.owner == new_wallet));
+}
+
+#[test]
+fn test_transfer_all_to_self_fails() {
+    let (_env, client, owner_a, _) = setup_env();
+
+    let result = client.try_transfer_all(&owner_a, &owner_a, &10);
+    assert_eq!(result.err().unwrap().unwrap(), TaskError::InvalidTaskData);
+}
+
+#[test]
+fn test_transfer_all_skips_closed_and_locked() {
+    let (env, client, owner_a, new_wallet) = setup_env();
+    let editor = Address::generate(&env);
+    let open = client.add_task(&String::from_str(&env, "Abierta"), &owner_a);
+    let done = client.add_task(&String::from_str(&env, "Hecha"), &owner_a);
+    client.task_completed(&done, &owner_a);
+    let locked = client.add_task(&String::from_str(&env, "Bloqueada"), &owner_a);
+    client.grant_edit(&locked, &owner_a, &editor, &(env.ledger().timestamp() + 3600));
+    client.lock_task(&locked, &editor, &10);
+
+    // Solo se mueve la pendiente: la concluida no es transferible y la otra la bloquea el editor
+    assert_eq!(client.transfer_all(&owner_a, &new_wallet, &10), 1);
+    assert_eq!(client.get_task_by_id(&open).unwrap().owner, new_wallet);
+    assert_eq!(client.get_task_by_id(&done).unwrap().owner, owner_a);
+    assert_eq!(client.get_task_by_id(&locked).unwrap().owner, owner_a);
+
+    // Al expirar el bloqueo, la tarea se mueve en la siguiente llamada
+    env.ledger().set_sequence_number(env.ledger().sequence() + 11);
+    assert_eq!(client.transfer_all(&owner_a, &new_wallet, &10), 1);
+    assert_eq!(client.get_task_by_id(&locked).unwrap().owner, new_wallet);
+    assert_eq!(client.transfer_all(&owner_a, &new_wallet, &10), 0);
+}
+
+// =======================================================
+// TEST: get_owner_digest
+// =======================================================
+
+#[test]
+fn test_owner_digest_changes_with_state() {
+    let (env, client, owner_a, owner_b) = setup_env();
+    let task_id = client.add_task(&String::from_str(&env, "Sincronizar caché"), &owner_a);
+
+    // El digest es determinista mientras no cambie el estado
+    let digest_1 = client.get_owner_digest(&owner_a);
+    assert_eq!(digest_1, client.get_owner_digest(&owner_a));
+
+    // Las tareas de otro propietario no afectan al digest
+    client.add_task(&String::from_str(&env, "Otra cuenta"), &owner_b);
+    assert_eq!(digest_1, client.get_owner_digest(&owner_a));
+
+    // Completar la tarea cambia el digest
+    client.task_completed(&task_id, &owner_a);
+    assert_ne!(digest_1, client.get_owner_digest(&owner_a));
+
+    // También cuentan las tareas del backlog, aunque no aparezcan en el listado activo
+    let idea = client.add_task(&String::from_str(&env, "Algún día"), &owner_a);
+    let digest_2 = client.get_owner_digest(&owner_a);
+    client.move_to_backlog(&idea, &owner_a);
+    assert_ne!(digest_2, client.get_owner_digest(&owner_a));
+}
+
+// =======================================================
+// TEST: get_state_root / get_proof
+// =======================================================
+
+#[test]
+fn test_merkle_proof_verifies_against_root() {
+    let (env, client, owner_a, _) = setup_env();
+    client.add_task(&String::from_str(&env, "T1"), &owner_a);
+    client.add_task(&String::from_str(&env, "T2"), &owner_a);
+    let t3_id = client.add_task(&String::from_str(&env, "T3"), &owner_a);
+
+    let root = client.get_state_root();
+    let proof = client.get_proof(&t3_id);
+    // 3 hojas rellenadas hasta 4 => 2 niveles
+    assert_eq!(proof.len(), 2);
+
+    // Recalcular la raíz desde la hoja de T3, como haría un cliente ligero
+    let task = client.get_task_by_id(&t3_id).unwrap();
+    let mut leaf_data = soroban_sdk::Bytes::from_array(&env, &[0u8]);
+    leaf_data.append(&soroban_sdk::xdr::ToXdr::to_xdr(task, &env));
+    let mut node: soroban_sdk::BytesN<32> = env.crypto().sha256(&leaf_data).into();
+    let mut index = t3_id - 1;
+    for sibling in proof.iter() {
+        let mut data = soroban_sdk::Bytes::from_array(&env, &[1u8]);
+        if index % 2 == 0 {
+            data.append(&soroban_sdk::Bytes::from(&node));
+            data.append(&soroban_sdk::Bytes::from(&sibling));
+        } else {
+            data.append(&soroban_sdk::Bytes::from(&sibling));
+            data.append(&soroban_sdk::Bytes::from(&node));
+        }
+        node = env.crypto().sha256(&data).into();
+        index /= 2;
+    }
+    assert_eq!(node, root);
+
+    // Cualquier cambio en una tarea cambia la raíz
+    client.task_completed(&t3_id, &owner_a);
+    assert_ne!(root, client.get_state_root());
+}
+
+#[test]
+fn test_state_root_pages_match_full_root() {
+    let (env, client, owner_a, _) = setup_env();
+
+    for n in 0..10u64 {
+        if n > 0 {
+            let id = client.add_task(&String::from_str(&env, "Tarea"), &owner_a);
+            if id % 3 == 0 {
+                client.task_deleted(&id, &owner_a);
+            }
+        }
+
+        // Recorrer en tramos de 3 IDs, arrastrando el acumulador
+        let mut cursor = 1;
+        let mut frontier = soroban_sdk::Vec::new(&env);
+        let root = loop {
+            let page = client.get_state_root_page(&cursor, &3, &frontier);
+            frontier = page.frontier;
+            match page.next_cursor {
+                Some(next) => cursor = next,
+                None => break page.root.unwrap(),
+            }
+        };
+        assert_eq!(root, client.get_state_root(), "{} tareas", n);
+    }
+
+    // Un acumulador que no corresponde al cursor se rechaza
+    let result = client.try_get_state_root_page(&4, &3, &soroban_sdk::Vec::new(&env));
+    assert_eq!(result.err().unwrap().unwrap(), TaskError::InvalidTaskData);
+}
+
+#[test]
+fn test_get_proof_unknown_task_fails() {
+    let (_env, client, _, _) = setup_env();
+
+    let result = client.try_get_proof(&1);
+    assert_eq!(result.err().unwrap().unwrap(), TaskError::TaskNotFound);
+}
+
+// =======================================================
+// TEST: verify_integrity
+// =======================================================
+
+#[test]
+fn test_verify_integrity_detects_transfer_drift() {
+    let (env, client, owner_a, owner_b) = setup_env();
+    let task_id = client.add_task(&String::from_str(&env, "Transferida"), &owner_a);
+    client.add_task(&String::from_str(&env, "Se queda"), &owner_a);
+
+    // Un índice recién creado es consistente
+    let report = client.verify_integrity(&owner_a, &1, &10);
+    assert!(report.missing_ids.is_empty());
+    assert!(report.stale_ids.is_empty());
+    assert!(report.mismatched_ids.is_empty());
+
+    // 'transfer_ownership' no actualiza los índices
+    client.transfer_ownership(&task_id, &owner_a, &owner_b);
+
+    let report_a = client.verify_integrity(&owner_a, &1, &10);
+    assert_eq!(report_a.mismatched_ids.len(), 1);
+    assert_eq!(report_a.mismatched_ids.get(0).unwrap(), task_id);
+
+    let report_b = client.verify_integrity(&owner_b, &1, &10);
+    assert_eq!(report_b.missing_ids.len(), 1);
+    assert_eq!(report_b.missing_ids.get(0).unwrap(), task_id);
+
+    // Fuera del rango no se revisa nada
+    let report_b = client.verify_integrity(&owner_b, &2, &10);
+    assert!(report_b.missing_ids.is_empty());
+}
+
+#[test]
+fn test_verify_integrity_requires_bounded_range() {
+    let (_env, client, owner_a, _) = setup_env();
+
+    let result = client.try_verify_integrity(&owner_a, &5, &1);
+    assert_eq!(result.err().unwrap().unwrap(), TaskError::InvalidTaskData);
+    let result = client.try_verify_integrity(&owner_a, &1, &500);
+    assert!(result.is_ok());
+    let result = client.try_verify_integrity(&owner_a, &1, &501);
+    assert_eq!(result.err().unwrap().unwrap(), TaskError::PaginationRequired);
+}
+
+// =======================================================
+// TEST: rebuild_owner_index
+// =======================================================
+
+#[test]
+fn test_rebuild_owner_index_repairs_transfer_drift() {
+    let (env, client, owner_a, owner_b) = setup_env();
+    let task_id = client.add_task(&String::from_str(&env, "Transferida"), &owner_a);
+    client.transfer_ownership(&task_id, &owner_a, &owner_b);
+
+    // Reparar ambos índices
+    assert_eq!(client.rebuild_owner_index(&owner_a, &1, &10), 1);
+    assert_eq!(client.rebuild_owner_index(&owner_b, &1, &10), 1);
+
+    // Ya no hay discrepancias y una segunda pasada no cambia nada
+    let report_a = client.verify_integrity(&owner_a, &1, &10);
+    let report_b = client.verify_integrity(&owner_b, &1, &10);
+    assert!(report_a.mismatched_ids.is_empty());
+    assert!(report_b.missing_ids.is_empty());
+    assert_eq!(client.rebuild_owner_index(&owner_b, &1, &10), 0);
+    assert_eq!(client.get_tasks_by_owner(&owner_b).len(), 1);
+}
+
+#[test]
+fn test_rebuild_owner_index_invalid_range_fails() {
+    let (_env, client, owner_a, _) = setup_env();
+
+    let result = client.try_rebuild_owner_index(&owner_a, &5, &1);
+    assert_eq!(result.err().unwrap().unwrap(), TaskError::InvalidTaskData);
+}
+
+// =======================================================
+// TEST: prioridad y fecha límite
+// =======================================================
+
+#[test]
+fn test_set_task_priority_and_due_date() {
+    let (env, client, owner_a, _) = setup_env();
+    let task_id = client.add_task(&String::from_str(&env, "Declaración de impuestos"), &owner_a);
+
+    // Valores por defecto del esquema v2
+    let task = client.get_task_by_id(&task_id).unwrap();
+    assert_eq!(task.priority, TaskPriority::Medium);
+    assert_eq!(task.due_date, None);
+
+    env.ledger().set_timestamp(1678890000);
+    client.set_task_priority(&task_id, &owner_a, &TaskPriority::High);
+    client.set_task_due_date(&task_id, &owner_a, &Some(1679000000));
+
+    let task = client.get_task_by_id(&task_id).unwrap();
+    assert_eq!(task.priority, TaskPriority::High);
+    assert_eq!(task.due_date, Some(1679000000));
+    assert_eq!(task.updated_at, 1678890000);
+
+    // Una fecha límite en el pasado no es válida
+    let result = client.try_set_task_due_date(&task_id, &owner_a, &Some(1));
+    assert_eq!(result.err().unwrap().unwrap(), TaskError::InvalidTaskData);
+}
+
+// =======================================================
+// TEST: migrate_task / migrate_batch
+// =======================================================
+
+// Simula un despliegue v1: tareas guardadas con el esquema antiguo bajo su ID
+fn seed_v1_tasks(env: &Env, client: &ToDoListContractClient, owner: &Address, count: u32) {
+    env.as_contract(&client.address, || {
+        for id in 1..=count {
+            let old = TaskV1 {
+                id,
+                description: String::from_str(env, "Tarea v1"),
+                owner: owner.clone(),
+                status: TaskStatus::Pending,
+                timestamp: env.ledger().timestamp(),
+            };
+            env.storage().instance().set(&id, &old);
+        }
+        env.storage().instance().set(&symbol_short!("next_id"), &(count + 1));
+    });
+}
+
+#[test]
+fn test_migration_v1_to_v3() {
+    let (env, client, owner_a, _) = setup_env();
+    seed_v1_tasks(&env, &client, &owner_a, 4);
+
+    assert_eq!(client.get_storage_version(), 1);
+
+    // La primera lectura migra el registro v1
+    let task = client.get_task_by_id(&2).unwrap();
+    assert_eq!(task.priority, TaskPriority::Medium);
+    assert_eq!(task.updated_at, task.timestamp);
+
+    // Migrar una tarea suelta y luego el resto por lotes (la 2 ya se migró al leerla)
+    client.migrate_task(&1);
+    assert_eq!(client.migrate_batch(&1, &3), 1);
+    assert_eq!(client.get_storage_version(), 1);
+    assert_eq!(client.migrate_batch(&4, &10), 1);
+    assert_eq!(client.get_storage_version(), 3);
+
+    // Las tareas siguen accesibles y la migración queda cerrada
+    assert_eq!(client.get_all().len(), 4);
+    let result = client.try_migrate_batch(&1, &3);
+    assert_eq!(result.err().unwrap().unwrap(), TaskError::MigrationNotNeeded);
+}
+
+#[test]
+fn test_fresh_deployment_needs_no_migration() {
+    let (env, client, owner_a, _) = setup_env();
+    client.add_task(&String::from_str(&env, "Nueva"), &owner_a);
+
+    assert_eq!(client.get_storage_version(), 3);
+    let result = client.try_migrate_task(&1);
+    assert_eq!(result.err().unwrap().unwrap(), TaskError::MigrationNotNeeded);
+}
+
+// Simula un despliegue v2: claves con el ID de tarea como u32 y dependencia 2 -> 1
+fn seed_v2_tasks(env: &Env, client: &ToDoListContractClient, owner: &Address) {
+    env.as_contract(&client.address, || {
+        let storage = env.storage().instance();
+        for id in 1..=2u32 {
+            let old = TaskV2 {
+                id,
+                description: String::from_str(env, "Tarea v2"),
+                owner: owner.clone(),
+                status: TaskStatus::Pending,
+                timestamp: env.ledger().timestamp(),
+                priority: TaskPriority::High,
+                due_date: None,
+                updated_at: env.ledger().timestamp(),
+            };
+            storage.set(&LegacyKey::Task(id), &old);
+        }
+        storage.set(owner, &soroban_sdk::vec![env, 1u32, 2u32]);
+        storage.set(&DataKey::StatusBuckets(TaskStatus::Pending), &soroban_sdk::vec![env, 0u32]);
+        storage.set(&DataKey::StatusBucket(TaskStatus::Pending, 0), &soroban_sdk::vec![env, 1u32, 2u32]);
+        storage.set(&LegacyKey::Dependencies(2), &soroban_sdk::vec![env, 1u32]);
+        storage.set(&LegacyKey::Dependents(1), &soroban_sdk::vec![env, 2u32]);
+        storage.set(&symbol_short!("st_ver"), &2u32);
+        storage.set(&symbol_short!("next_id"), &3u32);
+    });
+}
+
+#[test]
+fn test_migration_v2_to_v3() {
+    let (env, client, owner_a, _) = setup_env();
+    seed_v2_tasks(&env, &client, &owner_a);
+    assert_eq!(client.get_storage_version(), 2);
+
+    // La primera lectura migra la tarea y sus dependencias a IDs u64
+    let task = client.get_task_by_id(&2).unwrap();
+    assert_eq!(task.id, 2);
+    assert_eq!(task.priority, TaskPriority::High);
+    assert_eq!(client.get_dependencies(&2), soroban_sdk::vec![&env, 1u64]);
+    env.as_contract(&client.address, || {
+        assert!(!env.storage().instance().has(&LegacyKey::Task(2)));
+        assert!(env.storage().instance().has(&DataKey::Task(2)));
+    });
+
+    // El resto, por lotes; los índices compartidos ya leen IDs u64
+    assert_eq!(client.migrate_batch(&1, &2), 1);
+    assert_eq!(client.get_storage_version(), 3);
+    assert_eq!(client.get_dependents(&1), soroban_sdk::vec![&env, 2u64]);
+    assert_eq!(client.get_tasks_by_owner(&owner_a).len(), 2);
+
+    // Los IDs nuevos continúan tras los migrados
+    assert_eq!(client.add_task(&String::from_str(&env, "v3"), &owner_a), 3);
+    client.task_completed(&1, &owner_a);
+    client.task_completed(&2, &owner_a);
+}
+
+#[test]
+fn test_owner_listing_during_v2_migration() {
+    let (env, client, owner_a, _) = setup_env();
+    seed_v2_tasks(&env, &client, &owner_a);
+
+    // El índice del propietario se lee aunque aún guarde IDs u32
+    let tasks = client.get_tasks_by_owner(&owner_a);
+    assert_eq!(tasks.len(), 2);
+    assert_eq!(tasks.get(1).unwrap().id, 2);
+}
+
+#[test]
+fn test_verify_integrity_during_v2_migration_does_not_migrate() {
+    let (env, client, owner_a, _) = setup_env();
+    seed_v2_tasks(&env, &client, &owner_a);
+
+    let report = client.verify_integrity(&owner_a, &1, &10);
+    assert!(report.missing_ids.is_empty());
+    assert!(report.stale_ids.is_empty());
+    assert!(report.mismatched_ids.is_empty());
+
+    // Los registros siguen en el esquema v2
+    env.as_contract(&client.address, || {
+        assert!(env.storage().instance().has(&LegacyKey::Task(1)));
+        assert!(!env.storage().instance().has(&DataKey::Task(1)));
+    });
+}
+
+// =======================================================
+// TEST: testutils
+// =======================================================
+
+#[test]
+fn test_seed_tasks_and_raw_status() {
+    let (env, client, owner_a, _) = setup_env();
+
+    let ids = crate::testutils::seed_tasks(&env, &client.address, &owner_a, 50);
+    assert_eq!(ids.len(), 50);
+    assert_eq!(client.get_tasks_by_owner(&owner_a).len(), 50);
+
+    // El seed mantiene los índices por estado y la numeración por propietario
+    assert_eq!(client.get_all_paged(&TaskStatus::Pending, &0, &100).tasks.len(), 50);
+    assert_eq!(client.get_task(&owner_a, &50).unwrap().id, 50);
+
+    // La API pública continúa con el siguiente ID
+    let next = client.add_task(&String::from_str(&env, "Después del seed"), &owner_a);
+    assert_eq!(next, 51);
+
+    crate::testutils::set_task_status_raw(&env, &client.address, 7, TaskStatus::Deleted);
+    assert_eq!(client.get_task_by_id(&7).unwrap().status, TaskStatus::Deleted);
+    assert_eq!(client.get_tasks_by_owner(&owner_a).len(), 50);
+}
+
+#[test]
+fn test_check_invariants_after_operations() {
+    let (env, client, owner_a, owner_b) = setup_env();
+    let mut next_id = crate::testutils::check_invariants(&env, &client.address, 1);
+
+    let t1 = client.add_task(&String::from_str(&env, "T1"), &owner_a);
+    next_id = crate::testutils::check_invariants(&env, &client.address, next_id);
+
+    client.add_task(&String::from_str(&env, "T2"), &owner_b);
+    client.transfer_ownership(&t1, &owner_a, &owner_b);
+    client.task_completed(&t1, &owner_b);
+    next_id = crate::testutils::check_invariants(&env, &client.address, next_id);
+
+    // Los IDs archivados en frío o purgados no rompen los invariantes
+    client.archive_to_cold(&t1, &owner_b);
+    let t3 = client.add_task(&String::from_str(&env, "T3"), &owner_a);
+    client.task_deleted(&t3, &owner_a);
+    env.ledger().set_timestamp(env.ledger().timestamp() + client.get_purge_grace());
+    client.purge_task(&t3, &owner_a);
+    next_id = crate::testutils::check_invariants(&env, &client.address, next_id);
+
+    assert_eq!(next_id, 4);
+}
+
+// =======================================================
+// TEST: tareas programadas
+// =======================================================
+
+#[test]
+fn test_scheduled_task_hidden_until_activation() {
+    let (env, client, owner_a, _) = setup_env();
+    let start = env.ledger().timestamp() + 7 * 24 * 3600; // la semana que viene
+    let task_id = client.add_scheduled_task(&String::from_str(&env, "Empezar dieta"), &owner_a, &start);
+
+    // Antes de la activación no aparece en los listados
+    assert_eq!(client.get_task_by_id(&task_id).unwrap().status, TaskStatus::Scheduled);
+    assert_eq!(client.get_tasks_by_owner(&owner_a).len(), 0);
+    assert_eq!(client.get_all().len(), 0);
+    assert_eq!(client.activate_scheduled(&10), 0);
+
+    // Al pasar la hora se lee como Pending aunque no haya pasado un keeper
+    env.ledger().set_timestamp(start);
+    assert_eq!(client.get_task_by_id(&task_id).unwrap().status, TaskStatus::Pending);
+    assert_eq!(client.get_tasks_by_owner(&owner_a).len(), 1);
+
+    // El keeper la persiste como Pending
+    assert_eq!(client.activate_scheduled(&10), 1);
+    assert_eq!(client.activate_scheduled(&10), 0);
+    assert!(client.get_scheduled_for(&task_id).is_none());
+}
+
+#[test]
+fn test_scheduled_task_in_the_past_fails() {
+    let (env, client, owner_a, _) = setup_env();
+
+    let result = client.try_add_scheduled_task(&String::from_str(&env, "Ayer"), &owner_a, &env.ledger().timestamp());
+    assert_eq!(result.err().unwrap().unwrap(), TaskError::InvalidTaskData);
+}
+
+// =======================================================
+// TEST: envejecimiento de prioridad
+// =======================================================
+
+#[test]
+fn test_priority_aging_lazy_and_keeper() {
+    let (env, client, owner_a, _) = setup_env();
+    let day: u64 = 24 * 3600;
+    let task_id = client.add_task(&String::from_str(&env, "Llamar al banco"), &owner_a);
+
+    // Sin opt-in no hay envejecimiento
+    env.ledger().set_timestamp(env.ledger().timestamp() + 3 * day);
+    assert_eq!(client.get_effective_priority(&task_id), Some(TaskPriority::Medium));
+    assert_eq!(client.escalate_stale(&owner_a, &10), 0);
+
+    // Con opt-in (1 nivel por día) se aplica de forma perezosa...
+    client.set_priority_aging(&owner_a, &day);
+    assert_eq!(client.get_effective_priority(&task_id), Some(TaskPriority::Critical));
+    assert_eq!(client.get_task_by_id(&task_id).unwrap().priority, TaskPriority::Medium);
+
+    // ...y el keeper la persiste una sola vez
+    assert_eq!(client.escalate_stale(&owner_a, &10), 1);
+    assert_eq!(client.get_task_by_id(&task_id).unwrap().priority, TaskPriority::Critical);
+    assert_eq!(client.escalate_stale(&owner_a, &10), 0);
+}
+
+#[test]
+fn test_priority_aging_ignores_completed_tasks() {
+    let (env, client, owner_a, _) = setup_env();
+    let task_id = client.add_task(&String::from_str(&env, "Hecha"), &owner_a);
+    client.task_completed(&task_id, &owner_a);
+    client.set_priority_aging(&owner_a, &60);
+
+    env.ledger().set_timestamp(env.ledger().timestamp() + 600);
+    assert_eq!(client.get_effective_priority(&task_id), Some(TaskPriority::Medium));
+    assert_eq!(client.escalate_stale(&owner_a, &10), 0);
+}
+
+// =======================================================
+// TEST: block_task / unblock_task
+// =======================================================
+
+#[test]
+fn test_block_and_unblock_task() {
+    let (env, client, owner_a, _) = setup_env();
+    let task_id = client.add_task(&String::from_str(&env, "Desplegar"), &owner_a);
+    let reason = String::from_str(&env, "Esperando aprobación de seguridad");
+
+    client.block_task(&task_id, &owner_a, &reason);
+
+    let detail = client.get_task_detail(&task_id).unwrap();
+    assert_eq!(detail.task.status, TaskStatus::Blocked);
+    assert_eq!(detail.blocked_reason, Some(reason));
+
+    // Una tarea bloqueada sigue visible (no es lo mismo que eliminada)
+    assert_eq!(client.get_tasks_by_owner(&owner_a).len(), 1);
+
+    client.unblock_task(&task_id, &owner_a);
+    let detail = client.get_task_detail(&task_id).unwrap();
+    assert_eq!(detail.task.status, TaskStatus::Pending);
+    assert_eq!(detail.blocked_reason, None);
+}
+
+#[test]
+fn test_block_task_invalid_cases_fail() {
+    let (env, client, owner_a, _) = setup_env();
+    let task_id = client.add_task(&String::from_str(&env, "Revisar"), &owner_a);
+
+    // Motivo vacío
+    let result = client.try_block_task(&task_id, &owner_a, &String::from_str(&env, ""));
+    assert_eq!(result.err().unwrap().unwrap(), TaskError::InvalidTaskData);
+
+    // Desbloquear una tarea que no está bloqueada
+    let result = client.try_unblock_task(&task_id, &owner_a);
+    assert_eq!(result.err().unwrap().unwrap(), TaskError::InvalidTaskData);
+
+    // Bloquear una tarea completada
+    client.task_completed(&task_id, &owner_a);
+    let result = client.try_block_task(&task_id, &owner_a, &String::from_str(&env, "Tarde"));
+    assert_eq!(result.err().unwrap().unwrap(), TaskError::TaskAlreadyCompleted);
+}
+
+// =======================================================
+// TEST: cancel_task
+// =======================================================
+
+#[test]
+fn test_cancel_task_stays_visible_and_is_terminal() {
+    let (env, client, owner_a, _) = setup_env();
+    let task_id = client.add_task(&String::from_str(&env, "Viaje aplazado"), &owner_a);
+
+    client.cancel_task(&task_id, &owner_a);
+    assert_eq!(client.get_task_by_id(&task_id).unwrap().status, TaskStatus::Cancelled);
+
+    // Sigue visible en los listados, a diferencia de las eliminadas
+    assert_eq!(client.get_tasks_by_owner(&owner_a).len(), 1);
+    assert_eq!(client.get_all().len(), 1);
+
+    // Estado terminal: no se puede completar ni volver a cancelar
+    let result = client.try_task_completed(&task_id, &owner_a);
+    assert_eq!(result.err().unwrap().unwrap(), TaskError::TaskCancelled);
+    let result = client.try_cancel_task(&task_id, &owner_a);
+    assert_eq!(result.err().unwrap().unwrap(), TaskError::TaskCancelled);
+}
+
+#[test]
+fn test_cancel_completed_task_fails() {
+    let (env, client, owner_a, _) = setup_env();
+    let task_id = client.add_task(&String::from_str(&env, "Ya hecha"), &owner_a);
+    client.task_completed(&task_id, &owner_a);
+
+    let result = client.try_cancel_task(&task_id, &owner_a);
+    assert_eq!(result.err().unwrap().unwrap(), TaskError::TaskAlreadyCompleted);
+}
+
+// =======================================================
+// TEST: grant_edit / revoke_edit
+// =======================================================
+
+#[test]
+fn test_grant_edit_allows_until_expiry() {
+    let (env, client, owner_a, helper) = setup_env();
+    let task_id = client.add_task(&String::from_str(&env, "Borrador"), &owner_a);
+    let expires_at = env.ledger().timestamp() + 3600;
+
+    client.grant_edit(&task_id, &owner_a, &helper, &expires_at);
+
+    // El editor puede cambiar la descripción, pero no completar la tarea
+    let new_desc = String::from_str(&env, "Borrador revisado");
+    client.update_task_description(&task_id, &helper, &new_desc);
+    assert_eq!(client.get_task_by_id(&task_id).unwrap().description, new_desc);
+    let result = client.try_task_completed(&task_id, &helper);
+    assert_eq!(result.err().unwrap().unwrap(), TaskError::Unauthorized);
+
+    // Al caducar, el permiso deja de valer sin ninguna transacción de limpieza
+    env.ledger().set_timestamp(expires_at);
+    let result = client.try_update_task_description(&task_id, &helper, &String::from_str(&env, "Tarde"));
+    assert_eq!(result.err().unwrap().unwrap(), TaskError::Unauthorized);
+}
+
+#[test]
+fn test_revoke_edit() {
+    let (env, client, owner_a, helper) = setup_env();
+    let task_id = client.add_task(&String::from_str(&env, "Compartida"), &owner_a);
+    client.grant_edit(&task_id, &owner_a, &helper, &(env.ledger().timestamp() + 3600));
+
+    client.revoke_edit(&task_id, &owner_a, &helper);
+    assert!(client.get_edit_grant(&task_id, &helper).is_none());
+
+    let result = client.try_update_task_description(&task_id, &helper, &String::from_str(&env, "No"));
+    assert_eq!(result.err().unwrap().unwrap(), TaskError::Unauthorized);
+}
+
+#[test]
+fn test_grant_edit_by_non_owner_fails() {
+    let (env, client, owner_a, other_user) = setup_env();
+    let task_id = client.add_task(&String::from_str(&env, "Ajena"), &owner_a);
+
+    let result = client.try_grant_edit(&task_id, &other_user, &other_user, &(env.ledger().timestamp() + 60));
+    assert_eq!(result.err().unwrap().unwrap(), TaskError::Unauthorized);
+}
+
+// =======================================================
+// TEST: pick_random_pending
+// =======================================================
+
+#[test]
+fn test_pick_random_pending_only_returns_pending() {
+    let (env, client, owner_a, _) = setup_env();
+
+    // Sin tareas pendientes no hay nada que elegir
+    assert!(client.pick_random_pending(&owner_a).is_none());
+
+    let done = client.add_task(&String::from_str(&env, "Hecha"), &owner_a);
+    client.task_completed(&done, &owner_a);
+    assert!(client.pick_random_pending(&owner_a).is_none());
+
+    client.add_task(&String::from_str(&env, "Leer"), &owner_a);
+    client.add_task(&String::from_str(&env, "Correr"), &owner_a);
+    for _ in 0..5 {
+        let picked = client.pick_random_pending(&owner_a).unwrap();
+        assert_eq!(picked.status, TaskStatus::Pending);
+        assert_ne!(picked.id, done);
+    }
+}
+
+// =======================================================
+// TEST: pomodoros
+// =======================================================
+
+#[test]
+fn test_pomodoro_accumulates_focus_stats() {
+    let (env, client, owner_a, _) = setup_env();
+    let task_id = client.add_task(&String::from_str(&env, "Escribir informe"), &owner_a);
+    let start = env.ledger().timestamp();
+
+    client.start_pomodoro(&task_id, &owner_a, &1500);
+
+    // No se puede empezar otro ni cerrarlo antes de tiempo
+    let result = client.try_start_pomodoro(&task_id, &owner_a, &1500);
+    assert_eq!(result.err().unwrap().unwrap(), TaskError::PomodoroInProgress);
+    let result = client.try_complete_pomodoro(&task_id, &owner_a);
+    assert_eq!(result.err().unwrap().unwrap(), TaskError::PomodoroInProgress);
+
+    env.ledger().set_timestamp(start + 1500);
+    client.complete_pomodoro(&task_id, &owner_a);
+
+    // Segunda sesión
+    client.start_pomodoro(&task_id, &owner_a, &600);
+    env.ledger().set_timestamp(start + 3000);
+    client.complete_pomodoro(&task_id, &owner_a);
+
+    let task_stats = client.get_task_focus_stats(&task_id);
+    assert_eq!(task_stats.sessions, 2);
+    assert_eq!(task_stats.focused_secs, 2100);
+    assert_eq!(client.get_focus_stats(&owner_a), task_stats);
+}
+
+#[test]
+fn test_complete_pomodoro_without_start_fails() {
+    let (env, client, owner_a, _) = setup_env();
+    let task_id = client.add_task(&String::from_str(&env, "Sin sesión"), &owner_a);
+
+    let result = client.try_complete_pomodoro(&task_id, &owner_a);
+    assert_eq!(result.err().unwrap().unwrap(), TaskError::NoActivePomodoro);
+}
+
+#[test]
+fn test_pomodoro_duration_is_capped() {
+    let (env, client, owner_a, _) = setup_env();
+    let task_id = client.add_task(&String::from_str(&env, "Sesión larga"), &owner_a);
+
+    let result = client.try_start_pomodoro(&task_id, &owner_a, &u64::MAX);
+    assert_eq!(result.err().unwrap().unwrap(), TaskError::InvalidTaskData);
+    let result = client.try_start_pomodoro(&task_id, &owner_a, &(4 * 3600 + 1));
+    assert_eq!(result.err().unwrap().unwrap(), TaskError::InvalidTaskData);
+    client.start_pomodoro(&task_id, &owner_a, &(4 * 3600));
+}
+
+#[test]
+fn test_cancel_pomodoro_discards_session() {
+    let (env, client, owner_a, _) = setup_env();
+    let task_id = client.add_task(&String::from_str(&env, "Interrumpida"), &owner_a);
+    let start = env.ledger().timestamp();
+
+    client.start_pomodoro(&task_id, &owner_a, &1500);
+    env.ledger().set_timestamp(start + 1500);
+    client.cancel_pomodoro(&task_id, &owner_a);
+
+    // No se acumula tiempo y se puede empezar otra sesión
+    assert_eq!(client.get_task_focus_stats(&task_id).sessions, 0);
+    let result = client.try_complete_pomodoro(&task_id, &owner_a);
+    assert_eq!(result.err().unwrap().unwrap(), TaskError::NoActivePomodoro);
+    let result = client.try_cancel_pomodoro(&task_id, &owner_a);
+    assert_eq!(result.err().unwrap().unwrap(), TaskError::NoActivePomodoro);
+    client.start_pomodoro(&task_id, &owner_a, &600);
+}
+
+// =======================================================
+// TEST: hábitos
+// =======================================================
+
+#[test]
+fn test_habit_streaks() {
+    let (env, client, owner_a, _) = setup_env();
+    let day: u64 = 24 * 3600;
+    let habit_id = client.add_habit(&String::from_str(&env, "Meditar"), &owner_a);
+    let start = env.ledger().timestamp(); // 00:00 UTC
+
+    // Día 1 y día 2: la racha crece
+    assert_eq!(client.check_in(&habit_id).current_streak, 1);
+    let result = client.try_check_in(&habit_id);
+    assert_eq!(result.err().unwrap().unwrap(), TaskError::AlreadyCheckedIn);
+    env.ledger().set_timestamp(start + day);
+    assert_eq!(client.check_in(&habit_id).current_streak, 2);
+
+    // Saltarse un día reinicia la racha actual pero conserva la mejor
+    env.ledger().set_timestamp(start + 3 * day);
+    let state = client.check_in(&habit_id);
+    assert_eq!(state.current_streak, 1);
+    assert_eq!(state.best_streak, 2);
+    assert_eq!(state.total_check_ins, 3);
+}
+
+#[test]
+fn test_habit_day_boundary_uses_utc_offset() {
+    let (env, client, owner_a, _) = setup_env();
+    let habit_id = client.add_habit(&String::from_str(&env, "Leer"), &owner_a);
+    let start = env.ledger().timestamp(); // 00:00 UTC
+
+    // En UTC-5 las 23:00 UTC del día anterior y las 01:00 UTC son el mismo día local
+    client.set_utc_offset(&owner_a, &(-5 * 3600));
+    env.ledger().set_timestamp(start - 3600);
+    client.check_in(&habit_id);
+    env.ledger().set_timestamp(start + 3600);
+    let result = client.try_check_in(&habit_id);
+    assert_eq!(result.err().unwrap().unwrap(), TaskError::AlreadyCheckedIn);
+}
+
+#[test]
+fn test_check_in_regular_task_fails() {
+    let (env, client, owner_a, _) = setup_env();
+    let task_id = client.add_task(&String::from_str(&env, "Normal"), &owner_a);
+
+    let result = client.try_check_in(&task_id);
+    assert_eq!(result.err().unwrap().unwrap(), TaskError::NotAHabit);
+}
+
+// =======================================================
+// TEST: objetivos
+// =======================================================
+
+#[test]
+fn test_goal_progress_from_linked_tasks() {
+    let (env, client, owner_a, _) = setup_env();
+    let deadline = env.ledger().timestamp() + 30 * 24 * 3600;
+    let goal_id = client.create_goal(&owner_a, &String::from_str(&env, "Leer 2 libros"), &2, &deadline);
+
+    let t1 = client.add_task(&String::from_str(&env, "Libro 1"), &owner_a);
+    let t2 = client.add_task(&String::from_str(&env, "Libro 2"), &owner_a);
+    let unlinked = client.add_task(&String::from_str(&env, "Otra cosa"), &owner_a);
+    client.link_task_to_goal(&goal_id, &t1, &owner_a);
+    client.link_task_to_goal(&goal_id, &t2, &owner_a);
+
+    // Completar tareas no vinculadas no cuenta
+    client.task_completed(&unlinked, &owner_a);
+    client.task_completed(&t1, &owner_a);
+    let status = client.get_goals(&owner_a).get(0).unwrap();
+    assert_eq!(status.goal.progress, 1);
+    assert!(!status.achieved);
+
+    client.task_completed(&t2, &owner_a);
+    let status = client.get_goals(&owner_a).get(0).unwrap();
+    assert!(status.achieved);
+    assert!(!status.past_deadline);
+
+    // Pasada la fecha límite se indica en el estado
+    env.ledger().set_timestamp(deadline + 1);
+    assert!(client.get_goals(&owner_a).get(0).unwrap().past_deadline);
+}
+
+#[test]
+fn test_link_task_to_other_owners_goal_fails() {
+    let (env, client, owner_a, owner_b) = setup_env();
+    let deadline = env.ledger().timestamp() + 3600;
+    let goal_id = client.create_goal(&owner_a, &String::from_str(&env, "Mío"), &1, &deadline);
+    let task_id = client.add_task(&String::from_str(&env, "De B"), &owner_b);
+
+    let result = client.try_link_task_to_goal(&goal_id, &task_id, &owner_b);
+    assert_eq!(result.err().unwrap().unwrap(), TaskError::Unauthorized);
+
+    let result = client.try_link_task_to_goal(&99, &task_id, &owner_b);
+    assert_eq!(result.err().unwrap().unwrap(), TaskError::GoalNotFound);
+}
+
+// =======================================================
+// TEST: informe semanal
+// =======================================================
+
+#[test]
+fn test_weekly_report_counts() {
+    let (env, client, owner_a, _) = setup_env();
+    let week1 = env.ledger().timestamp();
+    let day = 24 * 3600;
+
+    let t1 = client.add_task(&String::from_str(&env, "Uno"), &owner_a);
+    let t2 = client.add_task(&String::from_str(&env, "Dos"), &owner_a);
+    client.add_task(&String::from_str(&env, "Tres"), &owner_a);
+    client.task_completed(&t1, &owner_a);
+    client.set_task_due_date(&t2, &owner_a, &Some(week1 + 2 * day + 100));
+
+    // Ya en la semana siguiente
+    env.ledger().set_timestamp(week1 + 8 * day);
+    client.add_task(&String::from_str(&env, "Cuatro"), &owner_a);
+
+    let report = client.get_weekly_report(&owner_a, &week1);
+    assert_eq!(report.created, 3);
+    assert_eq!(report.completed, 1);
+    assert_eq!(report.overdue, 1);
+    assert_eq!(report.carried_over, 0);
+
+    let report = client.get_weekly_report(&owner_a, &(week1 + 7 * day));
+    assert_eq!(report.created, 1);
+    assert_eq!(report.completed, 0);
+    assert_eq!(report.overdue, 0);
+    assert_eq!(report.carried_over, 2);
+}
+
+#[test]
+fn test_weekly_report_follows_transfers() {
+    let (env, client, owner_a, owner_b) = setup_env();
+    let week1 = env.ledger().timestamp();
+    let day = 24 * 3600;
+    let owner_c = Address::generate(&env);
+
+    let t1 = client.add_task(&String::from_str(&env, "Uno"), &owner_a);
+    let t2 = client.add_task(&String::from_str(&env, "Dos"), &owner_a);
+    client.set_task_due_date(&t1, &owner_a, &Some(week1 + 2 * day + 100));
+
+    // Las tareas abiertas y sus vencimientos pasan al nuevo propietario, por cualquier vía
+    client.transfer_ownership(&t1, &owner_a, &owner_b);
+    client.transfer_all(&owner_a, &owner_c, &10);
+
+    env.ledger().set_timestamp(week1 + 8 * day);
+    let next_week = week1 + 7 * day;
+    let report = client.get_weekly_report(&owner_a, &week1);
+    assert_eq!(report.created, 2);
+    assert_eq!(report.overdue, 0);
+    assert_eq!(client.get_weekly_report(&owner_a, &next_week).carried_over, 0);
+
+    let report = client.get_weekly_report(&owner_b, &week1);
+    assert_eq!(report.created, 0);
+    assert_eq!(report.overdue, 1);
+    assert_eq!(client.get_weekly_report(&owner_b, &next_week).carried_over, 1);
+    assert_eq!(client.get_weekly_report(&owner_c, &next_week).carried_over, 1);
+
+    // Al concluirla, el nuevo propietario deja de tenerla abierta
+    client.task_completed(&t2, &owner_c);
+    env.ledger().set_timestamp(week1 + 15 * day);
+    assert_eq!(client.get_weekly_report(&owner_c, &(week1 + 14 * day)).carried_over, 0);
+}
+
+// =======================================================
+// TEST: checkpoint y número de secuencia de los eventos
+// =======================================================
+
+#[test]
+fn test_checkpoint_counts_events() {
+    let (env, client, owner_a, _) = setup_env();
+    assert_eq!(client.get_checkpoint().1, 0);
+
+    let t1 = client.add_task(&String::from_str(&env, "Uno"), &owner_a);
+    client.add_task(&String::from_str(&env, "Dos"), &owner_a);
+    client.task_completed(&t1, &owner_a);
+
+    let (ledger_seq, event_seq) = client.get_checkpoint();
+    assert_eq!(ledger_seq, env.ledger().sequence() as u64);
+    assert_eq!(event_seq, 3);
+
+    // Una operación fallida no consume número de secuencia
+    let _ = client.try_task_completed(&t1, &owner_a);
+    assert_eq!(client.get_checkpoint().1, 3);
+
+    // Ni un cambio que no emite evento: los números de los eventos siguen sin saltos
+    let session = Address::generate(&env);
+    client.set_session_key(&owner_a, &session, &(env.ledger().sequence() + 100));
+    assert_eq!(client.get_checkpoint().1, 3);
+}
+
+// =======================================================
+// TEST: purga tras el periodo de gracia
+// =======================================================
+
+#[test]
+fn test_purge_respects_grace_period() {
+    let (env, client, owner_a, _) = setup_env();
+    let task_id = client.add_task(&String::from_str(&env, "Borrar"), &owner_a);
+
+    // Solo se purgan tareas ya eliminadas
+    let result = client.try_purge_task(&task_id, &owner_a);
+    assert_eq!(result.err().unwrap().unwrap(), TaskError::InvalidTaskData);
+
+    client.task_deleted(&task_id, &owner_a);
+    let result = client.try_purge_task(&task_id, &owner_a);
+    assert_eq!(result.err().unwrap().unwrap(), TaskError::PurgeTooSoon);
+
+    env.ledger().set_timestamp(env.ledger().timestamp() + client.get_purge_grace());
+    client.purge_task(&task_id, &owner_a);
+    assert_eq!(client.get_task_by_id(&task_id), None);
+    assert_eq!(client.get_task_seq(&task_id), None);
+    assert_eq!(client.get_task(&owner_a, &1), None);
+}
+
+// =======================================================
+// TEST: deshacer la última modificación
+// =======================================================
+
+#[test]
+fn test_undo_restores_previous_version() {
+    let (env, client, owner_a, _) = setup_env();
+    let task_id = client.add_task(&String::from_str(&env, "Original"), &owner_a);
+
+    // Recién creada no hay nada que deshacer
+    let result = client.try_undo(&task_id, &owner_a);
+    assert_eq!(result.err().unwrap().unwrap(), TaskError::NothingToUndo);
+
+    client.update_task_description(&task_id, &owner_a, &String::from_str(&env, "Error de dedo"));
+    client.undo(&task_id, &owner_a);
+    assert_eq!(client.get_task_by_id(&task_id).unwrap().description, String::from_str(&env, "Original"));
+
+    // Un único nivel: no se puede volver a deshacer
+    let result = client.try_undo(&task_id, &owner_a);
+    assert_eq!(result.err().unwrap().unwrap(), TaskError::NothingToUndo);
+}
+
+#[test]
+fn test_undo_window_expires() {
+    let (env, client, owner_a, _) = setup_env();
+    let task_id = client.add_task(&String::from_str(&env, "Tarea"), &owner_a);
+    client.task_completed(&task_id, &owner_a);
+
+    env.ledger().set_timestamp(env.ledger().timestamp() + 301);
+    let result = client.try_undo(&task_id, &owner_a);
+    assert_eq!(result.err().unwrap().unwrap(), TaskError::NothingToUndo);
+    assert_eq!(client.get_task_by_id(&task_id).unwrap().status, TaskStatus::Completed);
+}
+
+#[test]
+fn test_undo_ignores_keepers_and_reverts_stats() {
+    let (env, client, owner_a, _) = setup_env();
+    let task_id = client.add_task(&String::from_str(&env, "Original"), &owner_a);
+    client.set_priority_aging(&owner_a, &60);
+    client.update_task_description(&task_id, &owner_a, &String::from_str(&env, "Error de dedo"));
+
+    // El keeper no sustituye la instantánea del propietario
+    env.ledger().set_timestamp(env.ledger().timestamp() + 120);
+    assert_eq!(client.escalate_stale(&owner_a, &10), 1);
+    client.undo(&task_id, &owner_a);
+    assert_eq!(client.get_task_by_id(&task_id).unwrap().description, String::from_str(&env, "Original"));
+
+    // Deshacer una conclusión la descuenta de las estadísticas
+    client.task_completed(&task_id, &owner_a);
+    assert_eq!(client.get_pending_stats().completed, 1);
+    client.undo(&task_id, &owner_a);
+    assert_eq!(client.get_task_by_id(&task_id).unwrap().status, TaskStatus::Pending);
+    assert_eq!(client.get_pending_stats().completed, 0);
+}
+
+#[test]
+fn test_undo_rejected_after_side_entry_changes() {
+    let (env, client, owner_a, _) = setup_env();
+
+    // Revelar borra el hash: volver a la descripción vacía dejaría la tarea sin poder revelarse
+    let secret = soroban_sdk::Bytes::from_slice(&env, b"Regalo sorpresa");
+    let private_id = client.add_task_private(&env.crypto().sha256(&secret).into(), &owner_a);
+    let description = String::from_str(&env, "Regalo sorpresa");
+    client.reveal_description(&private_id, &owner_a, &description);
+    let result = client.try_undo(&private_id, &owner_a);
+    assert_eq!(result.err().unwrap().unwrap(), TaskError::NothingToUndo);
+    assert_eq!(client.get_task_by_id(&private_id).unwrap().description, description);
+
+    // Tras cambiar el contexto, deshacer no revierte una edición anterior dejando el contexto nuevo
+    let task_id = client.add_task(&String::from_str(&env, "Original"), &owner_a);
+    client.update_task_description(&task_id, &owner_a, &String::from_str(&env, "Editada"));
+    client.set_task_context(&task_id, &owner_a, &Some(TaskContext::Home));
+    let result = client.try_undo(&task_id, &owner_a);
+    assert_eq!(result.err().unwrap().unwrap(), TaskError::NothingToUndo);
+    assert_eq!(client.get_task_by_id(&task_id).unwrap().description, String::from_str(&env, "Editada"));
+    assert_eq!(client.get_task_context(&task_id), Some(TaskContext::Home));
+}
+
+// =======================================================
+// TEST: bloqueo de edición exclusiva
+// =======================================================
+
+#[test]
+fn test_lock_task_blocks_other_writers() {
+    let (env, client, owner_a, editor) = setup_env();
+    let task_id = client.add_task(&String::from_str(&env, "Compartida"), &owner_a);
+    client.grant_edit(&task_id, &owner_a, &editor, &(env.ledger().timestamp() + 3600));
+
+    client.lock_task(&task_id, &editor, &10);
+    assert_eq!(client.get_task_lock(&task_id).unwrap().holder, editor);
+
+    // El propietario no puede modificarla mientras dure el bloqueo del editor
+    let result = client.try_task_completed(&task_id, &owner_a);
+    assert_eq!(result.err().unwrap().unwrap(), TaskError::TaskLocked);
+
+    // El titular sí
+    client.update_task_description(&task_id, &editor, &String::from_str(&env, "Editada"));
+
+    // Al expirar, el resto vuelve a poder escribir
+    env.ledger().set_sequence_number(env.ledger().sequence() + 11);
+    assert_eq!(client.get_task_lock(&task_id), None);
+    client.task_completed(&task_id, &owner_a);
+}
+
+#[test]
+fn test_owner_can_release_lock() {
+    let (env, client, owner_a, editor) = setup_env();
+    let task_id = client.add_task(&String::from_str(&env, "Compartida"), &owner_a);
+    client.grant_edit(&task_id, &owner_a, &editor, &(env.ledger().timestamp() + 3600));
+    client.lock_task(&task_id, &editor, &100);
+
+    client.unlock_task(&task_id, &owner_a);
+    client.task_completed(&task_id, &owner_a);
+}
+
+// =======================================================
+// TEST: la firma queda ligada a la tarea y a los valores nuevos
+// =======================================================
+
+#[test]
+fn test_update_auth_bound_to_task_and_value() {
+    let (env, client, owner_a, _) = setup_env();
+    env.mock_all_auths();
+    let task_id = client.add_task(&String::from_str(&env, "Original"), &owner_a);
+    let new_description = String::from_str(&env, "Nueva");
+
+    client.update_task_description(&task_id, &owner_a, &new_description);
+
+    assert_eq!(
+        env.auths(),
+        std::vec![(
+            owner_a.clone(),
+            AuthorizedInvocation {
+                function: AuthorizedFunction::Contract((
+                    client.address.clone(),
+                    Symbol::new(&env, "update_task_description"),
+                    (task_id, new_description.clone()).into_val(&env),
+                )),
+                sub_invocations: std::vec![],
+            }
+        )]
+    );
+}
+
+// =======================================================
+// TEST: nonces de los delegados
+// =======================================================
+
+#[test]
+fn test_delegated_mutation_consumes_nonce() {
+    let (env, client, owner_a, session) = setup_env();
+    env.mock_all_auths();
+    client.set_session_key(&owner_a, &session, &(env.ledger().sequence() + 100));
+    let task_id = client.add_task(&String::from_str(&env, "Delegada"), &owner_a);
+    assert_eq!(client.get_nonce(&session), 0);
+
+    client.task_completed(&task_id, &session);
+
+    // La firma del delegado incluye su nonce, que queda consumido
+    assert_eq!(
+        env.auths(),
+        std::vec![(
+            session.clone(),
+            AuthorizedInvocation {
+                function: AuthorizedFunction::Contract((
+                    client.address.clone(),
+                    Symbol::new(&env, "task_completed"),
+                    (task_id, 0u64).into_val(&env),
+                )),
+                sub_invocations: std::vec![],
+            }
+        )]
+    );
+    assert_eq!(client.get_nonce(&session), 1);
+
+    // El propietario firma sin nonce
+    let other_id = client.add_task(&String::from_str(&env, "Propia"), &owner_a);
+    client.task_completed(&other_id, &owner_a);
+    assert_eq!(client.get_nonce(&owner_a), 0);
+}
+
+// =======================================================
+// TEST: referidos
+// =======================================================
+
+#[test]
+fn test_referrer_rewarded_after_first_completions() {
+    let (env, client, new_user, referrer) = setup_env();
+    client.register_referral(&new_user, &referrer);
+
+    for i in 0..3u32 {
+        let task_id = client.add_task(&String::from_str(&env, "Tarea"), &new_user);
+        client.task_completed(&task_id, &new_user);
+        let expected = if i < 2 { 0 } else { 10 };
+        assert_eq!(client.get_referral_points(&referrer), expected);
+    }
+
+    // Solo se recompensa una vez
+    let task_id = client.add_task(&String::from_str(&env, "Otra"), &new_user);
+    client.task_completed(&task_id, &new_user);
+    assert_eq!(client.get_referral_points(&referrer), 10);
+}
+
+#[test]
+fn test_invalid_referrals_rejected() {
+    let (env, client, owner_a, owner_b) = setup_env();
+
+    let result = client.try_register_referral(&owner_a, &owner_a);
+    assert_eq!(result.err().unwrap().unwrap(), TaskError::InvalidReferral);
+
+    // Referido circular
+    client.register_referral(&owner_a, &owner_b);
+    let result = client.try_register_referral(&owner_b, &owner_a);
+    assert_eq!(result.err().unwrap().unwrap(), TaskError::InvalidReferral);
+
+    // Un usuario con tareas ya no es nuevo
+    let existing = Address::generate(&env);
+    client.add_task(&String::from_str(&env, "Vieja"), &existing);
+    let result = client.try_register_referral(&existing, &owner_b);
+    assert_eq!(result.err().unwrap().unwrap(), TaskError::InvalidReferral);
+}
+
+// =======================================================
+// TEST: acciones de administración con timelock
+// =======================================================
+
+#[test]
+fn test_admin_action_waits_for_timelock() {
+    let (env, client, _, _) = setup_env();
+
+    let action_id = client.schedule_action(&AdminAction::SetPurgeGrace(60));
+    let result = client.try_execute_action(&action_id);
+    assert_eq!(result.err().unwrap().unwrap(), TaskError::TimelockNotElapsed);
+
+    env.ledger().set_timestamp(client.get_pending_action(&action_id).unwrap().eta);
+    client.execute_action(&action_id);
+    assert_eq!(client.get_purge_grace(), 60);
+
+    // Ya ejecutada, no queda pendiente
+    let result = client.try_execute_action(&action_id);
+    assert_eq!(result.err().unwrap().unwrap(), TaskError::ActionNotFound);
+}
+
+#[test]
+fn test_cancelled_action_cannot_execute() {
+    let (env, client, _, _) = setup_env();
+
+    // Acortar la espera también pasa por el timelock
+    let action_id = client.schedule_action(&AdminAction::SetActionDelay(3600));
+    env.ledger().set_timestamp(client.get_pending_action(&action_id).unwrap().eta);
+    client.execute_action(&action_id);
+    assert_eq!(client.get_action_delay(), 3600);
+
+    let action_id = client.schedule_action(&AdminAction::SetPurgeGrace(0));
+    client.cancel_action(&action_id);
+    env.ledger().set_timestamp(env.ledger().timestamp() + 3600);
+    let result = client.try_execute_action(&action_id);
+    assert_eq!(result.err().unwrap().unwrap(), TaskError::ActionNotFound);
+}
+
+#[test]
+fn test_admin_reassign_after_timelock() {
+    let (env, client, owner_a, recovered) = setup_env();
+    let task_id = client.add_task(&String::from_str(&env, "Perdida"), &owner_a);
+
+    let result = client.try_admin_reassign(&task_id, &recovered, &String::from_str(&env, ""));
+    assert_eq!(result.err().unwrap().unwrap(), TaskError::InvalidTaskData);
+
+    let reason = String::from_str(&env, "Transferida por error");
+    let action_id = client.admin_reassign(&task_id, &recovered, &reason);
+    assert_eq!(client.get_task_by_id(&task_id).unwrap().owner, owner_a);
+
+    env.ledger().set_timestamp(client.get_pending_action(&action_id).unwrap().eta);
+    client.execute_action(&action_id);
+
+    assert_eq!(client.get_task_by_id(&task_id).unwrap().owner, recovered);
+    assert_eq!(client.get_tasks_by_owner(&recovered).len(), 1);
+    assert_eq!(client.get_tasks_by_owner(&owner_a).len(), 0);
+    let record = client.get_recovery(&task_id).unwrap();
+    assert_eq!(record.previous_owner, owner_a);
+    assert_eq!(record.reason, reason);
+}
+
+// =======================================================
+// TEST: roles
+// =======================================================
+
+#[test]
+fn test_pauser_role_can_freeze_tasks() {
+    let (env, client, owner_a, pauser) = setup_env();
+    let task_id = client.add_task(&String::from_str(&env, "Disputada"), &owner_a);
+
+    let result = client.try_freeze_task(&task_id, &pauser);
+    assert_eq!(result.err().unwrap().unwrap(), TaskError::Unauthorized);
+
+    client.grant_role(&Role::Pauser, &pauser);
+    assert!(client.has_role(&Role::Pauser, &pauser));
+    assert!(!client.has_role(&Role::Upgrader, &pauser));
+    client.freeze_task(&task_id, &pauser);
+
+    // El propietario no puede levantar un congelamiento ajeno
+    let result = client.try_unfreeze_task(&task_id, &owner_a);
+    assert_eq!(result.err().unwrap().unwrap(), TaskError::Unauthorized);
+
+    client.unfreeze_task(&task_id, &pauser);
+    client.revoke_role(&Role::Pauser, &pauser);
+    let result = client.try_freeze_task(&task_id, &pauser);
+    assert_eq!(result.err().unwrap().unwrap(), TaskError::Unauthorized);
+}
+
+#[test]
+fn test_only_upgrader_schedules_upgrade() {
+    let (env, client, _, upgrader) = setup_env();
+    let wasm_hash = soroban_sdk::BytesN::from_array(&env, &[7; 32]);
+
+    let result = client.try_schedule_upgrade(&upgrader, &wasm_hash);
+    assert_eq!(result.err().unwrap().unwrap(), TaskError::Unauthorized);
+
+    client.grant_role(&Role::Upgrader, &upgrader);
+    let action_id = client.schedule_upgrade(&upgrader, &wasm_hash);
+    assert_eq!(client.get_pending_action(&action_id).unwrap().action, AdminAction::Upgrade(wasm_hash));
+}
+
+// =======================================================
+// TEST: moderación
+// =======================================================
+
+#[test]
+fn test_moderator_hides_from_public_listing() {
+    let (env, client, owner_a, moderator) = setup_env();
+    client.grant_role(&Role::Moderator, &moderator);
+    let task_id = client.add_task(&String::from_str(&env, "Spam"), &owner_a);
+
+    client.moderate_hide(&task_id, &moderator, &String::from_str(&env, "Spam"));
+    assert!(client.is_hidden(&task_id));
+    assert_eq!(client.get_all().len(), 0);
+    // El propietario la sigue viendo
+    assert_eq!(client.get_tasks_by_owner(&owner_a).len(), 1);
+
+    client.moderate_unhide(&task_id, &moderator);
+    assert_eq!(client.get_all().len(), 1);
+}
+
+#[test]
+fn test_non_moderator_cannot_hide() {
+    let (env, client, owner_a, other) = setup_env();
+    let task_id = client.add_task(&String::from_str(&env, "Normal"), &owner_a);
+
+    let result = client.try_moderate_hide(&task_id, &other, &String::from_str(&env, "No"));
+    assert_eq!(result.err().unwrap().unwrap(), TaskError::Unauthorized);
+}
+
+// =======================================================
+// TEST: descripciones privadas (hash comprometido)
+// =======================================================
+
+#[test]
+fn test_private_task_reveal_checks_hash() {
+    let (env, client, owner_a, _) = setup_env();
+    let secret = soroban_sdk::Bytes::from_slice(&env, b"Regalo sorpresa");
+    let desc_hash: soroban_sdk::BytesN<32> = env.crypto().sha256(&secret).into();
+
+    let task_id = client.add_task_private(&desc_hash, &owner_a);
+    assert_eq!(client.get_task_by_id(&task_id).unwrap().description.len(), 0);
+    assert_eq!(client.get_description_hash(&task_id), Some(desc_hash));
+
+    let result = client.try_reveal_description(&task_id, &owner_a, &String::from_str(&env, "Otra cosa"));
+    assert_eq!(result.err().unwrap().unwrap(), TaskError::HashMismatch);
+
+    let description = String::from_str(&env, "Regalo sorpresa");
+    client.reveal_description(&task_id, &owner_a, &description);
+    assert_eq!(client.get_task_by_id(&task_id).unwrap().description, description);
+    assert_eq!(client.get_description_hash(&task_id), None);
+}
+
+// =======================================================
+// TEST: descripciones cifradas
+// =======================================================
+
+#[test]
+fn test_encrypted_task_detail() {
+    let (env, client, owner_a, _) = setup_env();
+    let pubkey = soroban_sdk::BytesN::from_array(&env, &[9; 32]);
+    client.set_encryption_pubkey(&owner_a, &pubkey);
+    assert_eq!(client.get_encryption_pubkey(&owner_a), Some(pubkey));
+
+    let ciphertext = soroban_sdk::Bytes::from_slice(&env, &[1, 2, 3, 4]);
+    let encrypted_id = client.add_task_encrypted(&ciphertext, &owner_a);
+    let plain_id = client.add_task(&String::from_str(&env, "En claro"), &owner_a);
+
+    // Ambas se consultan igual; solo la cifrada trae texto cifrado
+    let detail = client.get_task_detail(&encrypted_id).unwrap();
+    assert_eq!(detail.description_encrypted, Some(ciphertext));
+    assert_eq!(detail.task.description.len(), 0);
+    assert_eq!(client.get_task_detail(&plain_id).unwrap().description_encrypted, None);
+
+    let result = client.try_add_task_encrypted(&soroban_sdk::Bytes::new(&env), &owner_a);
+    assert_eq!(result.err().unwrap().unwrap(), TaskError::InvalidTaskData);
+}
+
+// =======================================================
+// TEST: conclusión con compromiso (preimagen)
+// =======================================================
+
+#[test]
+fn test_complete_with_proof_requires_preimage() {
+    let (env, client, owner_a, _) = setup_env();
+    let task_id = client.add_task(&String::from_str(&env, "Entregar clave"), &owner_a);
+    let secret = soroban_sdk::Bytes::from_slice(&env, b"clave-del-entregable");
+    let commitment: soroban_sdk::BytesN<32> = env.crypto().sha256(&secret).into();
+    client.set_completion_commitment(&task_id, &owner_a, &commitment);
+
+    let result = client.try_task_completed(&task_id, &owner_a);
+    assert_eq!(result.err().unwrap().unwrap(), TaskError::ProofRequired);
+
+    let wrong = soroban_sdk::Bytes::from_slice(&env, b"otra");
+    let result = client.try_complete_with_proof(&task_id, &owner_a, &wrong);
+    assert_eq!(result.err().unwrap().unwrap(), TaskError::HashMismatch);
+
+    client.complete_with_proof(&task_id, &owner_a, &secret);
+    assert_eq!(client.get_task_by_id(&task_id).unwrap().status, TaskStatus::Completed);
+    assert_eq!(client.get_completion_proof(&task_id), Some(secret));
+}
+
+// =======================================================
+// TEST: importar tareas de un despliegue anterior
+// =======================================================
+
+// Contrato de origen con el esquema v1
+#[soroban_sdk::contract]
+struct LegacyBoard;
+
+#[soroban_sdk::contractimpl]
+impl LegacyBoard {
+    pub fn seed(env: Env, tasks: soroban_sdk::Vec<TaskV1>) {
+        for task in tasks.iter() {
+            env.storage().instance().set(&task.id, &task);
+        }
+    }
+
+    pub fn get_task_by_id(env: Env, task_id: u32) -> Option<TaskV1> {
+        env.storage().instance().get(&task_id)
+    }
+}
+
+#[test]
+fn test_import_tasks_from_legacy_contract() {
+    let (env, client, owner_a, _) = setup_env();
+    env.mock_all_auths();
+    let legacy_id = env.register(LegacyBoard, ());
+    let legacy = LegacyBoardClient::new(&env, &legacy_id);
+
+    let old_task = |id: u32, desc: &str, status: TaskStatus| TaskV1 {
+        id,
+        description: String::from_str(&env, desc),
+        owner: owner_a.clone(),
+        status,
+        timestamp: 1_600_000_000,
+    };
+    legacy.seed(&soroban_sdk::vec![
+        &env,
+        old_task(7, "Vieja pendiente", TaskStatus::Pending),
+        old_task(8, "Vieja hecha", TaskStatus::Completed),
+        old_task(9, "Vieja borrada", TaskStatus::Deleted),
+    ]);
+
+    // Ya hay una tarea aquí: los IDs importados se reasignan
+    client.add_task(&String::from_str(&env, "Nueva"), &owner_a);
+    let new_ids = client.import_tasks(&legacy_id, &soroban_sdk::vec![&env, 7, 8, 9]);
+    assert_eq!(new_ids, soroban_sdk::vec![&env, 2, 3]);
+
+    let imported = client.get_task_by_id(&3).unwrap();
+    assert_eq!(imported.description, String::from_str(&env, "Vieja hecha"));
+    assert_eq!(imported.status, TaskStatus::Completed);
+    assert_eq!(imported.owner, owner_a);
+
+    let result = client.try_import_tasks(&legacy_id, &soroban_sdk::vec![&env, 99]);
+    assert_eq!(result.err().unwrap().unwrap(), TaskError::TaskNotFound);
+}
+
+// =======================================================
+// TEST: exportación en formato estable
+// =======================================================
+
+#[test]
+fn test_export_range_flattens_fields() {
+    let (env, client, owner_a, _) = setup_env();
+    let t1 = client.add_task(&String::from_str(&env, "Uno"), &owner_a);
+    let t2 = client.add_task(&String::from_str(&env, "Dos"), &owner_a);
+    client.task_completed(&t1, &owner_a);
+    client.set_task_priority(&t2, &owner_a, &TaskPriority::Critical);
+
+    // El rango puede pasar del último ID
+    let exported = client.export_range(&1, &50);
+    assert_eq!(exported.len(), 2);
+    let first = exported.get(0).unwrap();
+    assert_eq!(first.status, 1);
+    assert_eq!(first.due_date, 0);
+    assert_eq!(exported.get(1).unwrap().priority, 3);
+
+    let result = client.try_export_range(&1, &101);
+    assert_eq!(result.err().unwrap().unwrap(), TaskError::InvalidTaskData);
+}
+
+// =======================================================
+// TEST: interfaz TaskBoard
+// =======================================================
+
+#[test]
+fn test_task_board_client_talks_to_contract() {
+    let (env, client, owner_a, owner_b) = setup_env();
+    env.mock_all_auths();
+    let board = crate::interface::TaskBoardClient::new(&env, &client.address);
+
+    let task_id = board.add_task(&String::from_str(&env, "Genérica"), &owner_a);
+    board.transfer_ownership(&task_id, &owner_a, &owner_b);
+    board.task_completed(&task_id, &owner_b);
+
+    let task = board.get_task_by_id(&task_id).unwrap();
+    assert_eq!(task.status, TaskStatus::Completed);
+    assert_eq!(task.owner, owner_b);
+}
+
+// =======================================================
+// TEST: conclusión verificada por oráculo
+// =======================================================
+
+// Oráculo de prueba: solo confirma la afirmación "ok"
+#[soroban_sdk::contract]
+struct MockOracle;
+
+#[soroban_sdk::contractimpl]
+impl MockOracle {
+    pub fn verify(env: Env, _task_id: u64, claim: soroban_sdk::Bytes) -> bool {
+        claim == soroban_sdk::Bytes::from_slice(&env, b"ok")
+    }
+}
+
+#[test]
+fn test_oracle_gates_completion() {
+    let (env, client, owner_a, _) = setup_env();
+    env.mock_all_auths();
+    let oracle = env.register(MockOracle, ());
+
+    let task_id = client.add_task(&String::from_str(&env, "PR fusionado"), &owner_a);
+    let claim = soroban_sdk::Bytes::from_slice(&env, b"pendiente");
+
+    // Solo se admiten oráculos registrados
+    let result = client.try_set_task_oracle(&task_id, &owner_a, &oracle, &claim);
+    assert_eq!(result.err().unwrap().unwrap(), TaskError::InvalidTaskData);
+
+    client.set_oracle(&oracle, &true);
+    client.set_task_oracle(&task_id, &owner_a, &oracle, &claim);
+    let result = client.try_task_completed(&task_id, &owner_a);
+    assert_eq!(result.err().unwrap().unwrap(), TaskError::OracleRejected);
+
+    let other_id = client.add_task(&String::from_str(&env, "Feed actualizado"), &owner_a);
+    client.set_task_oracle(&other_id, &owner_a, &oracle, &soroban_sdk::Bytes::from_slice(&env, b"ok"));
+    client.task_completed(&other_id, &owner_a);
+    assert_eq!(client.get_task_by_id(&other_id).unwrap().status, TaskStatus::Completed);
+}
+
+// =======================================================
+// TEST: envío de estadísticas al contrato compañero
+// =======================================================
+
+// Contrato de estadísticas de prueba: acumula lo recibido
+#[soroban_sdk::contract]
+struct MockStatsSink;
+
+#[soroban_sdk::contractimpl]
+impl MockStatsSink {
+    pub fn record_stats(env: Env, _source: Address, stats: StatsCounters) {
+        let mut total: StatsCounters = env.storage().instance().get(&symbol_short!("total")).unwrap_or_default();
+        total.created += stats.created;
+        total.completed += stats.completed;
+        env.storage().instance().set(&symbol_short!("total"), &total);
+    }
+
+    pub fn total(env: Env) -> StatsCounters {
+        env.storage().instance().get(&symbol_short!("total")).unwrap_or_default()
+    }
+}
+
+#[test]
+fn test_flush_stats_to_sink() {
+    let (env, client, owner_a, _) = setup_env();
+    env.mock_all_auths();
+    let sink_id = env.register(MockStatsSink, ());
+    let sink = MockStatsSinkClient::new(&env, &sink_id);
+
+    let task_id = client.add_task(&String::from_str(&env, "Uno"), &owner_a);
+    client.add_task(&String::from_str(&env, "Dos"), &owner_a);
+    client.task_completed(&task_id, &owner_a);
+
+    // Sin contrato configurado no se envía nada
+    assert!(!client.flush_stats());
+
+    let action_id = client.schedule_action(&AdminAction::SetStatsSink(Some(sink_id.clone())));
+    env.ledger().set_timestamp(client.get_pending_action(&action_id).unwrap().eta);
+    client.execute_action(&action_id);
+
+    assert!(client.flush_stats());
+    assert_eq!(sink.total().created, 2);
+    assert_eq!(sink.total().completed, 1);
+    assert_eq!(client.get_pending_stats(), StatsCounters::default());
+    assert!(!client.flush_stats());
+}
+
+// =======================================================
+// TEST: numeración por propietario
+// =======================================================
+
+#[test]
+fn test_owner_scoped_sequence() {
+    let (env, client, owner_a, owner_b) = setup_env();
+    let a1 = client.add_task(&String::from_str(&env, "A1"), &owner_a);
+    let b1 = client.add_task(&String::from_str(&env, "B1"), &owner_b);
+    let a2 = client.add_task(&String::from_str(&env, "A2"), &owner_a);
+
+    // Cada propietario numera desde 1, con independencia del ID global
+    assert_eq!(client.get_task(&owner_a, &1).unwrap().id, a1);
+    assert_eq!(client.get_task(&owner_a, &2).unwrap().id, a2);
+    assert_eq!(client.get_task(&owner_b, &1).unwrap().id, b1);
+    assert_eq!(client.get_task(&owner_b, &2), None);
+    assert_eq!(client.get_task_seq(&a2), Some((owner_a.clone(), 2)));
+}
+
+// =======================================================
+// TEST: contador de IDs sin desbordamiento
+// =======================================================
+
+#[test]
+fn test_task_id_counter_overflow() {
+    let (env, client, owner_a, _) = setup_env();
+    env.as_contract(&client.address, || {
+        env.storage().instance().set(&symbol_short!("next_id"), &u64::MAX);
+    });
+
+    let result = client.try_add_task(&String::from_str(&env, "Una de más"), &owner_a);
+    assert_eq!(result.err().unwrap().unwrap(), TaskError::CounterOverflow);
+}
+
+// =======================================================
+// TEST: listado global paginado y restringible
+// =======================================================
+
+#[test]
+fn test_get_all_page_walks_ids() {
+    let (env, client, owner_a, _) = setup_env();
+    for _ in 0..5 {
+        client.add_task(&String::from_str(&env, "Tarea"), &owner_a);
+    }
+    client.task_deleted(&2, &owner_a);
+
+    let page = client.get_all_page(&0, &3);
+    assert_eq!(page.tasks.len(), 2); // IDs 1 y 3 (la 2 está eliminada)
+    assert_eq!(page.next_cursor, Some(4));
+
+    let page = client.get_all_page(&4, &3);
+    assert_eq!(page.tasks.len(), 2);
+    assert_eq!(page.next_cursor, None);
+
+    let result = client.try_get_all_page(&0, &101);
+    assert_eq!(result.err().unwrap().unwrap(), TaskError::InvalidTaskData);
+}
+
+#[test]
+fn test_private_enumeration_requires_admin() {
+    let (env, client, owner_a, _) = setup_env();
+    client.add_task(&String::from_str(&env, "Privada"), &owner_a);
+
+    let action_id = client.schedule_action(&AdminAction::SetPublicEnumeration(false));
+    env.ledger().set_timestamp(client.get_pending_action(&action_id).unwrap().eta);
+    client.execute_action(&action_id);
+    assert!(!client.is_public_enumeration());
+
+    // El admin sigue pudiendo listar
+    assert_eq!(client.get_all().len(), 1);
+    assert_eq!(client.get_all_page(&1, &10).tasks.len(), 1);
+}
+
+// =======================================================
+// TEST: índice global por estado
+// =======================================================
+
+#[test]
+fn test_get_all_paged_by_status() {
+    let (env, client, owner_a, _) = setup_env();
+    for _ in 0..5 {
+        client.add_task(&String::from_str(&env, "Tarea"), &owner_a);
+    }
+    client.task_completed(&2, &owner_a);
+    client.task_completed(&4, &owner_a);
+    client.task_deleted(&5, &owner_a);
+
+    let page = client.get_all_paged(&TaskStatus::Pending, &0, &2);
+    assert_eq!(page.tasks.len(), 2); // IDs 1 y 3
+    assert_eq!(page.next_cursor, None);
+
+    let page = client.get_all_paged(&TaskStatus::Completed, &0, &1);
+    assert_eq!(page.tasks.get(0).unwrap().id, 2);
+    assert_eq!(page.next_cursor, Some(4));
+
+    let page = client.get_all_paged(&TaskStatus::Completed, &4, &1);
+    assert_eq!(page.tasks.get(0).unwrap().id, 4);
+    assert_eq!(page.next_cursor, None);
+
+    // Las tareas ya indexadas no se duplican
+    assert_eq!(client.reindex_status(&1, &5), 0);
+}
+
+// =======================================================
+// TEST: renovación automática del TTL
+// =======================================================
+
+#[test]
+fn test_reads_bump_instance_ttl() {
+    let (env, client, owner_a, _) = setup_env();
+    client.add_task(&String::from_str(&env, "Tarea viva"), &owner_a);
+    let (_, extend_to) = client.get_ttl_policy();
+
+    // Tras muchos ledgers sin escrituras, una lectura vuelve a ampliar el TTL
+    env.ledger().with_mut(|li| li.sequence_number += extend_to - 10);
+    client.get_task_detail(&1);
+    let ttl = env.as_contract(&client.address, || env.storage().instance().get_ttl());
+    assert_eq!(ttl, extend_to);
+}
+
+// =======================================================
+// TEST: archivo en frío
+// =======================================================
+
+#[test]
+fn test_archive_and_restore_from_cold() {
+    let (env, client, owner_a, _) = setup_env();
+    client.add_task(&String::from_str(&env, "Historial"), &owner_a);
+
+    // Las tareas abiertas no se archivan
+    let result = client.try_archive_to_cold(&1, &owner_a);
+    assert_eq!(result.err().unwrap().unwrap(), TaskError::InvalidTaskData);
+
+    client.task_completed(&1, &owner_a);
+    let original = client.get_task_by_id(&1).unwrap();
+    let hash = client.archive_to_cold(&1, &owner_a);
+
+    assert!(client.get_task_by_id(&1).is_none());
+    let tombstone = client.get_cold_tombstone(&1).unwrap();
+    assert_eq!(tombstone.hash, hash);
+    assert_eq!(tombstone.status, TaskStatus::Completed);
+
+    // Un registro alterado no coincide con el hash
+    let mut forged = original.clone();
+    forged.description = String::from_str(&env, "Otra cosa");
+    let result = client.try_restore_from_cold(&forged);
+    assert_eq!(result.err().unwrap().unwrap(), TaskError::HashMismatch);
+
+    client.restore_from_cold(&original);
+    assert_eq!(client.get_task_by_id(&1).unwrap(), original);
+    assert!(client.get_cold_tombstone(&1).is_none());
+}
+
+#[test]
+fn test_extend_ttls_by_anyone() {
+    let (env, client, owner_a, _) = setup_env();
+    client.add_task(&String::from_str(&env, "Tablero público"), &owner_a);
+
+    let max_ttl = env.as_contract(&client.address, || env.storage().max_ttl());
+    assert_eq!(client.extend_ttls(&soroban_sdk::vec![&env, 1, 99], &max_ttl), 1);
+    let ttl = env.as_contract(&client.address, || env.storage().instance().get_ttl());
+    assert_eq!(ttl, max_ttl);
+
+    let result = client.try_extend_ttls(&soroban_sdk::vec![&env, 99], &max_ttl);
+    assert_eq!(result.err().unwrap().unwrap(), TaskError::TaskNotFound);
+}
+
+// =======================================================
+// TEST: huella de almacenamiento
+// =======================================================
+
+#[test]
+fn test_estimate_footprint() {
+    let (env, client, owner_a, owner_b) = setup_env();
+    client.add_task(&String::from_str(&env, "Uno"), &owner_a);   // 3 bytes
+    client.add_task(&String::from_str(&env, "Dos"), &owner_a);   // 3 bytes
+    client.add_task(&String::from_str(&env, "Ajena"), &owner_b);
+    client.task_completed(&2, &owner_a);
+
+    let report = client.estimate_footprint(&owner_a);
+    assert_eq!(report.task_entries, 2);
+    assert_eq!(report.description_bytes, 6);
+    // Cada tarea tiene su TaskSeq; la completada, además, su UndoSnapshot
+    assert_eq!(report.side_entries, 3);
+    // Pending y Completed en el cubo 0
+    assert_eq!(report.index_buckets, 2);
+}
+
+#[test]
+fn test_estimate_footprint_drops_purged_sequence_numbers() {
+    let (env, client, owner_a, _) = setup_env();
+    let purged = client.add_task(&String::from_str(&env, "Purgada"), &owner_a);
+    client.add_task(&String::from_str(&env, "Se queda"), &owner_a);
+    client.task_deleted(&purged, &owner_a);
+    env.ledger().set_timestamp(env.ledger().timestamp() + 8 * 24 * 3600);
+
+    let before = client.estimate_footprint(&owner_a);
+    client.purge_task(&purged, &owner_a);
+    let after = client.estimate_footprint(&owner_a);
+
+    // Desaparece su OwnerSeq aunque el contador de secuencia del propietario no retroceda
+    assert_eq!(after.owner_entries, before.owner_entries - 1);
+    assert_eq!(after.task_entries, 1);
+}
+
+// =======================================================
+// TEST: eventos con estado anterior y nuevo
+// =======================================================
+
+#[test]
+fn test_transition_events_carry_old_and_new_status() {
+    let (env, client, owner_a, owner_b) = setup_env();
+    client.add_task(&String::from_str(&env, "Con eventos"), &owner_a);
+
+    client.transfer_ownership(&1, &owner_a, &owner_b);
+    let (_, _, data) = env.events().all().last().unwrap();
+    let (_, event_seq) = client.get_checkpoint();
+    let payload: (u64, u32, Address, Address) = data.into_val(&env);
+    assert_eq!(payload, (1, event_seq, owner_a, owner_b.clone()));
+
+    client.task_completed(&1, &owner_b);
+    let (_, topics, data) = env.events().all().last().unwrap();
+    let (_, event_seq) = client.get_checkpoint();
+    assert_eq!(topics, (symbol_short!("task"), symbol_short!("completed")).into_val(&env));
+    let payload: (u64, u32, TaskStatus, TaskStatus) = data.into_val(&env);
+    assert_eq!(payload, (1, event_seq, TaskStatus::Pending, TaskStatus::Completed));
+}
+
+// =======================================================
+// TEST: lectura con error explícito y papelera
+// =======================================================
+
+#[test]
+fn test_get_task_checked_distinguishes_deleted() {
+    let (env, client, owner_a, _) = setup_env();
+    client.add_task(&String::from_str(&env, "A la papelera"), &owner_a);
+    assert_eq!(client.get_task_checked(&1).id, 1);
+
+    client.task_deleted(&1, &owner_a);
+    let result = client.try_get_task_checked(&1);
+    assert_eq!(result.err().unwrap().unwrap(), TaskError::TaskIsDeleted);
+    assert_eq!(client.get_deleted_task(&1).status, TaskStatus::Deleted);
+
+    let result = client.try_get_task_checked(&99);
+    assert_eq!(result.err().unwrap().unwrap(), TaskError::TaskNotFound);
+    let result = client.try_get_deleted_task(&99);
+    assert_eq!(result.err().unwrap().unwrap(), TaskError::TaskNotFound);
+}
+
+// =======================================================
+// TEST: máquina de estados
+// =======================================================
+
+#[test]
+fn test_illegal_transitions_are_rejected() {
+    let (env, client, owner_a, _) = setup_env();
+    let done = client.add_task(&String::from_str(&env, "Hecha"), &owner_a);
+    let blocked = client.add_task(&String::from_str(&env, "Bloqueada"), &owner_a);
+
+    // Una tarea completada ya no se puede eliminar
+    client.task_completed(&done, &owner_a);
+    let result = client.try_task_deleted(&done, &owner_a);
+    assert_eq!(result.err().unwrap().unwrap(), TaskError::InvalidTransition);
+
+    // Una tarea bloqueada hay que desbloquearla antes de completarla
+    client.block_task(&blocked, &owner_a, &String::from_str(&env, "Esperando"));
+    let result = client.try_task_completed(&blocked, &owner_a);
+    assert_eq!(result.err().unwrap().unwrap(), TaskError::InvalidTransition);
+
+    // Ni se elimina dos veces
+    client.task_deleted(&blocked, &owner_a);
+    let result = client.try_task_deleted(&blocked, &owner_a);
+    assert_eq!(result.err().unwrap().unwrap(), TaskError::InvalidTransition);
+}
+
+// =======================================================
+// TEST: transferencia de tareas concluidas
+// =======================================================
+
+#[test]
+fn test_closed_tasks_not_transferable_by_default() {
+    let (env, client, owner_a, owner_b) = setup_env();
+    let done = client.add_task(&String::from_str(&env, "Hecha"), &owner_a);
+    let deleted = client.add_task(&String::from_str(&env, "Borrada"), &owner_a);
+    client.task_completed(&done, &owner_a);
+    client.task_deleted(&deleted, &owner_a);
+
+    let result = client.try_transfer_ownership(&done, &owner_a, &owner_b);
+    assert_eq!(result.err().unwrap().unwrap(), TaskError::TaskNotTransferable);
+    let result = client.try_transfer_ownership(&deleted, &owner_a, &owner_b);
+    assert_eq!(result.err().unwrap().unwrap(), TaskError::TaskNotTransferable);
+}
+
+#[test]
+fn test_closed_tasks_transferable_when_enabled() {
+    let (env, client, owner_a, owner_b) = setup_env();
+    let done = client.add_task(&String::from_str(&env, "Hecha"), &owner_a);
+    client.task_completed(&done, &owner_a);
+
+    let action_id = client.schedule_action(&AdminAction::SetTransferClosed(true));
+    env.ledger().set_timestamp(client.get_pending_action(&action_id).unwrap().eta);
+    client.execute_action(&action_id);
+    assert!(client.is_closed_transferable());
+
+    client.transfer_ownership(&done, &owner_a, &owner_b);
+    assert_eq!(client.get_task_by_id(&done).unwrap().owner, owner_b);
+}
+
+// =======================================================
+// TEST: operaciones sin efecto
+// =======================================================
+
+#[test]
+fn test_noop_update_and_self_transfer_rejected() {
+    let (env, client, owner_a, _) = setup_env();
+    let description = String::from_str(&env, "Igual");
+    let task_id = client.add_task(&description, &owner_a);
+    let (_, event_seq_before) = client.get_checkpoint();
+
+    let result = client.try_update_task_description(&task_id, &owner_a, &description);
+    assert_eq!(result.err().unwrap().unwrap(), TaskError::NoChange);
+
+    let result = client.try_transfer_ownership(&task_id, &owner_a, &owner_a);
+    assert_eq!(result.err().unwrap().unwrap(), TaskError::SelfTransfer);
+
+    // Ninguna de las dos emitió eventos
+    assert_eq!(client.get_checkpoint().1, event_seq_before);
+}
+
+// =======================================================
+// TEST: validación de descripciones
+// =======================================================
+
+#[test]
+fn test_descriptions_are_trimmed_and_validated() {
+    let (env, client, owner_a, _) = setup_env();
+
+    let task_id = client.add_task(&String::from_str(&env, "  Comprar pan \n"), &owner_a);
+    assert_eq!(client.get_task_by_id(&task_id).unwrap().description, String::from_str(&env, "Comprar pan"));
+
+    // Solo espacios, solo caracteres de control o demasiado corta
+    for bad in ["   ", "\u{7}\u{1b}", " x "] {
+        let result = client.try_add_task(&String::from_str(&env, bad), &owner_a);
+        assert_eq!(result.err().unwrap().unwrap(), TaskError::InvalidTaskData);
+    }
+
+    // Al actualizar también se normaliza: con espacios extra no es un cambio real
+    let result = client.try_update_task_description(&task_id, &owner_a, &String::from_str(&env, " Comprar pan "));
+    assert_eq!(result.err().unwrap().unwrap(), TaskError::NoChange);
+}
+
+// =======================================================
+// TEST: idioma de las tareas
+// =======================================================
+
+#[test]
+fn test_tasks_filtered_by_lang() {
+    let (env, client, owner_a, _) = setup_env();
+    let es = client.add_task_lang(&String::from_str(&env, "Comprar leche"), &owner_a, &symbol_short!("es"));
+    client.add_task_lang(&String::from_str(&env, "Buy milk"), &owner_a, &symbol_short!("en"));
+    client.add_task(&String::from_str(&env, "Sin idioma"), &owner_a);
+
+    let tasks = client.get_tasks_by_owner_lang(&owner_a, &symbol_short!("es"));
+    assert_eq!(tasks.len(), 1);
+    assert_eq!(tasks.get(0).unwrap().id, es);
+    assert_eq!(client.get_task_lang(&3), None);
+}
+
+// =======================================================
+// TEST: color e icono
+// =======================================================
+
+#[test]
+fn test_task_appearance_in_detail() {
+    let (env, client, owner_a, _) = setup_env();
+    let task_id = client.add_task(&String::from_str(&env, "Colorida"), &owner_a);
+
+    client.set_task_appearance(&task_id, &owner_a, &Some(0x33AA55), &Some(symbol_short!("cart")));
+    let detail = client.get_task_detail(&task_id).unwrap();
+    assert_eq!(detail.color, Some(0x33AA55));
+    assert_eq!(detail.icon, Some(symbol_short!("cart")));
+
+    let result = client.try_set_task_appearance(&task_id, &owner_a, &Some(0x1000000), &None);
+    assert_eq!(result.err().unwrap().unwrap(), TaskError::InvalidTaskData);
+
+    client.set_task_appearance(&task_id, &owner_a, &None, &None);
+    assert_eq!(client.get_task_detail(&task_id).unwrap().icon, None);
+}
+
+// =======================================================
+// TEST: ubicación de las tareas
+// =======================================================
+
+#[test]
+fn test_get_tasks_near_uses_grid() {
+    let (env, client, owner_a, owner_b) = setup_env();
+    let milk = client.add_task(&String::from_str(&env, "Comprar leche"), &owner_a);
+    let far = client.add_task(&String::from_str(&env, "Recoger paquete"), &owner_a);
+    let other = client.add_task(&String::from_str(&env, "Ajena"), &owner_b);
+
+    // Madrid (40.4168, -3.7038); la tienda a ~0.003° y el paquete a ~0.5°
+    client.set_task_location(&milk, &owner_a, &40_419_000, &-3_701_000);
+    client.set_task_location(&far, &owner_a, &40_900_000, &-3_700_000);
+    client.set_task_location(&other, &owner_b, &40_417_000, &-3_703_000);
+
+    let near = client.get_tasks_near(&owner_a, &40_416_800, &-3_703_800, &5_000);
+    assert_eq!(near.len(), 1);
+    assert_eq!(near.get(0).unwrap().id, milk);
+
+    // Sin ubicación ya no aparece
+    client.clear_task_location(&milk, &owner_a);
+    assert_eq!(client.get_tasks_near(&owner_a, &40_416_800, &-3_703_800, &5_000).len(), 0);
+
+    let result = client.try_get_tasks_near(&owner_a, &0, &0, &1_000_000);
+    assert_eq!(result.err().unwrap().unwrap(), TaskError::InvalidTaskData);
+}
+
+// =======================================================
+// TEST: contextos GTD
+// =======================================================
+
+#[test]
+fn test_tasks_by_context_and_counts() {
+    let (env, client, owner_a, _) = setup_env();
+    let milk = client.add_task(&String::from_str(&env, "Comprar leche"), &owner_a);
+    let call = client.add_task(&String::from_str(&env, "Llamar al banco"), &owner_a);
+    let stamps = client.add_task(&String::from_str(&env, "Comprar sellos"), &owner_a);
+
+    client.set_task_context(&milk, &owner_a, &Some(TaskContext::Errands));
+    client.set_task_context(&stamps, &owner_a, &Some(TaskContext::Errands));
+    client.set_task_context(&call, &owner_a, &Some(TaskContext::Phone));
+    client.task_completed(&stamps, &owner_a);
+
+    let errands = client.get_tasks_by_context(&owner_a, &TaskContext::Errands);
+    assert_eq!(errands.len(), 1);
+    assert_eq!(errands.get(0).unwrap().id, milk);
+
+    // Cambiar de contexto saca la tarea del anterior
+    client.set_task_context(&call, &owner_a, &Some(TaskContext::Office));
+    let counts = client.get_context_counts(&owner_a);
+    assert!(counts.contains((TaskContext::Errands, 1)));
+    assert!(counts.contains((TaskContext::Phone, 0)));
+    assert!(counts.contains((TaskContext::Office, 1)));
+}
+
+// =======================================================
+// TEST: sugerencias por energía y tiempo
+// =======================================================
+
+#[test]
+fn test_suggest_task_fits_energy_and_time() {
+    let (env, client, owner_a, _) = setup_env();
+    let email = client.add_task(&String::from_str(&env, "Responder correos"), &owner_a);
+    let report = client.add_task(&String::from_str(&env, "Escribir informe"), &owner_a);
+    let filing = client.add_task(&String::from_str(&env, "Archivar facturas"), &owner_a);
+
+    client.set_task_effort(&email, &owner_a, &EnergyLevel::Low, &15);
+    client.set_task_effort(&filing, &owner_a, &EnergyLevel::Low, &30);
+    client.set_task_effort(&report, &owner_a, &EnergyLevel::High, &90);
+
+    // Con mucha energía y tiempo, la tarea exigente
+    assert_eq!(client.suggest_task(&owner_a, &EnergyLevel::High, &120).unwrap().id, report);
+    // Con poca energía, la más larga de las que caben
+    assert_eq!(client.suggest_task(&owner_a, &EnergyLevel::Low, &60).unwrap().id, filing);
+    assert_eq!(client.suggest_task(&owner_a, &EnergyLevel::High, &20).unwrap().id, email);
+    assert_eq!(client.suggest_task(&owner_a, &EnergyLevel::Medium, &10), None);
+}
+
+// =======================================================
+// TEST: tareas a la espera de terceros
+// =======================================================
+
+#[test]
+fn test_waiting_on_lifecycle() {
+    let (env, client, owner_a, _) = setup_env();
+    env.ledger().set_timestamp(1_000);
+    let task_id = client.add_task(&String::from_str(&env, "Firmar contrato"), &owner_a);
+    let who = String::from_str(&env, "Asesoría");
+
+    client.mark_waiting_on(&task_id, &owner_a, &who, &900);
+    let waiting = client.get_waiting(&owner_a);
+    assert_eq!(waiting.len(), 1);
+    assert_eq!(waiting.get(0).unwrap().who, who);
+    assert_eq!(waiting.get(0).unwrap().since, 900);
+
+    // Una tarea en espera no se puede volver a poner en espera
+    let result = client.try_mark_waiting_on(&task_id, &owner_a, &who, &900);
+    assert_eq!(result.err().unwrap().unwrap(), TaskError::InvalidTransition);
+
+    client.stop_waiting(&task_id, &owner_a);
+    assert_eq!(client.get_task_by_id(&task_id).unwrap().status, TaskStatus::Pending);
+    assert!(client.get_waiting(&owner_a).is_empty());
+}
+
+// =======================================================
+// TEST: backlog (algún día / quizá)
+// =======================================================
+
+#[test]
+fn test_backlog_hidden_from_active_list() {
+    let (env, client, owner_a, _) = setup_env();
+    let idea = client.add_task(&String::from_str(&env, "Aprender a tocar el piano"), &owner_a);
+    client.add_task(&String::from_str(&env, "Pagar la luz"), &owner_a);
+
+    client.move_to_backlog(&idea, &owner_a);
+    assert_eq!(client.get_tasks_by_owner(&owner_a).len(), 1);
+    assert_eq!(client.get_backlog(&owner_a).get(0).unwrap().id, idea);
+
+    client.promote_from_backlog(&idea, &owner_a);
+    assert_eq!(client.get_tasks_by_owner(&owner_a).len(), 2);
+    assert!(client.get_backlog(&owner_a).is_empty());
+
+    // El ida y vuelta no cuenta como una tarea creada más
+    let report = client.get_weekly_report(&owner_a, &env.ledger().timestamp());
+    assert_eq!(report.created, 2);
+
+    let result = client.try_promote_from_backlog(&idea, &owner_a);
+    assert_eq!(result.err().unwrap().unwrap(), TaskError::InvalidTaskData);
+
+    // Una tarea aparcada no está concluida: no se archiva en frío
+    client.move_to_backlog(&idea, &owner_a);
+    let result = client.try_archive_to_cold(&idea, &owner_a);
+    assert_eq!(result.err().unwrap().unwrap(), TaskError::InvalidTaskData);
+}
+
+// =======================================================
+// TEST: revisión periódica
+// =======================================================
+
+#[test]
+fn test_due_for_review_ordering_and_reset() {
+    let (env, client, owner_a, _) = setup_env();
+    env.ledger().set_timestamp(1_000);
+    let weekly = client.add_task(&String::from_str(&env, "Revisar presupuesto"), &owner_a);
+    let daily = client.add_task(&String::from_str(&env, "Revisar bandeja"), &owner_a);
+    client.add_task(&String::from_str(&env, "Sin revisión"), &owner_a);
+
+    client.set_review_cadence(&weekly, &owner_a, &(7 * 86_400));
+    client.set_review_cadence(&daily, &owner_a, &86_400);
+    assert!(client.get_due_for_review(&owner_a, &10).is_empty());
+
+    // A los 8 días tocan las dos: primero la que venció antes
+    env.ledger().set_timestamp(1_000 + 8 * 86_400);
+    let due = client.get_due_for_review(&owner_a, &10);
+    assert_eq!(due.len(), 2);
+    assert_eq!(due.get(0).unwrap().id, daily);
+
+    client.mark_reviewed(&daily, &owner_a);
+    let due = client.get_due_for_review(&owner_a, &10);
+    assert_eq!(due.len(), 1);
+    assert_eq!(due.get(0).unwrap().id, weekly);
+}
+
+// =======================================================
+// TEST: dependencias entre propietarios
+// =======================================================
+
+#[test]
+fn test_external_dependency_needs_consent() {
+    let (env, client, owner_a, owner_b) = setup_env();
+    let design = client.add_task(&String::from_str(&env, "Diseño"), &owner_b);
+    let build = client.add_task(&String::from_str(&env, "Construcción"), &owner_a);
+    let deploy = client.add_task(&String::from_str(&env, "Despliegue"), &owner_a);
+
+    // Entre tareas propias la dependencia es inmediata
+    assert!(client.add_dependency(&deploy, &owner_a, &build));
+
+    // Sobre una tarea ajena queda pendiente hasta que su propietario acepte
+    assert!(!client.add_dependency(&build, &owner_a, &design));
+    assert!(client.get_dependents(&design).is_empty());
+    assert_eq!(client.get_dependency_requests(&design), soroban_sdk::vec![&env, build]);
+
+    client.approve_external_dependency(&design, &owner_b, &build);
+    assert_eq!(client.get_dependents(&design), soroban_sdk::vec![&env, build]);
+    assert_eq!(client.get_dependencies(&build), soroban_sdk::vec![&env, design]);
+    assert!(client.get_dependency_requests(&design).is_empty());
+
+    // No se permiten ciclos
+    let result = client.try_add_dependency(&build, &owner_a, &deploy);
+    assert_eq!(result.err().unwrap().unwrap(), TaskError::DependencyCycle);
+}
+
+#[test]
+fn test_approval_rechecks_dependency_limits() {
+    let (env, client, owner_a, owner_b) = setup_env();
+    let design = client.add_task(&String::from_str(&env, "Diseño"), &owner_b);
+    let build = client.add_task(&String::from_str(&env, "Construcción"), &owner_a);
+    assert!(!client.add_dependency(&build, &owner_a, &design));
+
+    // Mientras espera la aprobación, la tarea llena su cupo de dependencias
+    for _ in 0..20 {
+        let step = client.add_task(&String::from_str(&env, "Paso"), &owner_a);
+        client.add_dependency(&build, &owner_a, &step);
+    }
+    let result = client.try_approve_external_dependency(&design, &owner_b, &build);
+    assert_eq!(result.err().unwrap().unwrap(), TaskError::InvalidTaskData);
+    assert_eq!(client.get_dependencies(&build).len(), 20);
+}
+
+// =======================================================
+// TEST: camino crítico de un proyecto
+// =======================================================
+
+#[test]
+fn test_critical_path_follows_longest_chain() {
+    let (env, client, owner_a, _) = setup_env();
+    let project = client.create_goal(&owner_a, &String::from_str(&env, "Lanzamiento"), &4, &(env.ledger().timestamp() + 30 * 86_400));
+
+    let spec = client.add_task(&String::from_str(&env, "Especificar"), &owner_a);
+    let backend = client.add_task(&String::from_str(&env, "Backend"), &owner_a);
+    let docs = client.add_task(&String::from_str(&env, "Documentar"), &owner_a);
+    let release = client.add_task(&String::from_str(&env, "Publicar"), &owner_a);
+    for (task_id, minutes) in [(spec, 30), (backend, 60), (docs, 10), (release, 20)] {
+        client.link_task_to_goal(&project, &task_id, &owner_a);
+        client.set_task_effort(&task_id, &owner_a, &EnergyLevel::Medium, &minutes);
+    }
+    client.add_dependency(&backend, &owner_a, &spec);
+    client.add_dependency(&docs, &owner_a, &spec);
+    client.add_dependency(&release, &owner_a, &backend);
+    client.add_dependency(&release, &owner_a, &docs);
+
+    assert_eq!(client.get_critical_path(&project), soroban_sdk::vec![&env, spec, backend, release]);
+
+    let result = client.try_get_critical_path(&99);
+    assert_eq!(result.err().unwrap().unwrap(), TaskError::GoalNotFound);
+}
+
+#[test]
+fn test_timeline_chains_start_estimates() {
+    let (env, client, owner_a, _) = setup_env();
+    env.ledger().set_timestamp(10_000);
+    let project = client.create_goal(&owner_a, &String::from_str(&env, "Mudanza"), &3, &(10_000 + 30 * 86_400));
+
+    let boxes = client.add_task(&String::from_str(&env, "Comprar cajas"), &owner_a);
+    let pack = client.add_task(&String::from_str(&env, "Empaquetar"), &owner_a);
+    let quote = client.add_task(&String::from_str(&env, "Pedir presupuesto"), &owner_a);
+    for (task_id, minutes) in [(boxes, 60), (pack, 240), (quote, 15)] {
+        client.link_task_to_goal(&project, &task_id, &owner_a);
+        client.set_task_effort(&task_id, &owner_a, &EnergyLevel::Low, &minutes);
+    }
+    client.add_dependency(&pack, &owner_a, &boxes);
+    client.task_completed(&quote, &owner_a);
+
+    let timeline = client.get_timeline(&project);
+    assert_eq!(timeline.len(), 3);
+
+    let pack_entry = timeline.iter().find(|e| e.task_id == pack).unwrap();
+    assert_eq!(pack_entry.start_estimate, 10_000 + 60 * 60);
+    assert_eq!(pack_entry.duration_minutes, 240);
+    assert_eq!(pack_entry.dependencies, soroban_sdk::vec![&env, boxes]);
+
+    let quote_entry = timeline.iter().find(|e| e.task_id == quote).unwrap();
+    assert_eq!(quote_entry.start_estimate, 0);
+    assert_eq!(quote_entry.status, TaskStatus::Completed);
+}
+
+// =======================================================
+// TEST: dividir una tarea en subtareas
+// =======================================================
+
+#[test]
+fn test_split_task_modes() {
+    let (env, client, owner_a, _) = setup_env();
+    let trip = client.add_task(&String::from_str(&env, "Organizar viaje"), &owner_a);
+    let parts = soroban_sdk::vec![&env, String::from_str(&env, "Reservar vuelo"), String::from_str(&env, "Reservar hotel")];
+
+    // Como contenedor la original sigue pendiente
+    let children = client.split_task(&trip, &owner_a, &parts, &SplitMode::Container);
+    assert_eq!(children.len(), 2);
+    assert_eq!(client.get_subtasks(&trip), children);
+    assert_eq!(client.get_parent_task(&children.get(0).unwrap()), Some(trip));
+    assert_eq!(client.get_task_by_id(&trip).unwrap().status, TaskStatus::Pending);
+
+    // Reemplazándola, la original va a la papelera
+    let party = client.add_task(&String::from_str(&env, "Fiesta"), &owner_a);
+    client.split_task(&party, &owner_a, &parts, &SplitMode::Replace);
+    assert_eq!(client.get_task_by_id(&party).unwrap().status, TaskStatus::Deleted);
+
+    // Una parte inválida revierte toda la división
+    let bad = soroban_sdk::vec![&env, String::from_str(&env, "Válida"), String::from_str(&env, " ")];
+    let result = client.try_split_task(&trip, &owner_a, &bad, &SplitMode::Container);
+    assert_eq!(result.err().unwrap().unwrap(), TaskError::InvalidTaskData);
+    assert_eq!(client.get_subtasks(&trip).len(), 2);
+}
+
+// =======================================================
+// TEST: fusionar tareas duplicadas
+// =======================================================
+
+#[test]
+fn test_merge_tasks_links_both_ways() {
+    let (env, client, owner_a, owner_b) = setup_env();
+    let primary = client.add_task(&String::from_str(&env, "Pagar IBI"), &owner_a);
+    let duplicate = client.add_task(&String::from_str(&env, "Recibo del IBI"), &owner_a);
+    let foreign = client.add_task(&String::from_str(&env, "Ajena"), &owner_b);
+
+    client.merge_tasks(&primary, &duplicate, &owner_a);
+
+    assert_eq!(client.get_task_by_id(&primary).unwrap().description, String::from_str(&env, "Pagar IBI | Recibo del IBI"));
+    assert_eq!(client.get_task_by_id(&duplicate).unwrap().status, TaskStatus::Deleted);
+    assert_eq!(client.get_merged_into(&duplicate), Some(primary));
+    assert_eq!(client.get_merged_from(&primary), soroban_sdk::vec![&env, duplicate]);
+
+    // Solo entre tareas del mismo propietario
+    let result = client.try_merge_tasks(&primary, &foreign, &owner_a);
+    assert_eq!(result.err().unwrap().unwrap(), TaskError::Unauthorized);
+
+    // Un editor delegado de la principal no puede mandar otra tarea a la papelera
+    let other = client.add_task(&String::from_str(&env, "Otra del propietario"), &owner_a);
+    client.grant_edit(&primary, &owner_a, &owner_b, &(env.ledger().timestamp() + 3600));
+    let result = client.try_merge_tasks(&primary, &other, &owner_b);
+    assert_eq!(result.err().unwrap().unwrap(), TaskError::Unauthorized);
+    assert_eq!(client.get_task_by_id(&other).unwrap().status, TaskStatus::Pending);
+}
+
+// =======================================================
+// TEST: enlaces entre tareas
+// =======================================================
+
+#[test]
+fn test_link_tasks_is_bidirectional() {
+    let (env, client, owner_a, _) = setup_env();
+    let a = client.add_task(&String::from_str(&env, "Revisar contrato"), &owner_a);
+    let b = client.add_task(&String::from_str(&env, "Firmar contrato"), &owner_a);
+
+    client.link_tasks(&a, &b, &owner_a, &LinkType::Blocks);
+    assert_eq!(client.get_links(&a), soroban_sdk::vec![&env, TaskLink { task_id: b, relation: LinkType::Blocks, outgoing: true }]);
+    assert_eq!(client.get_links(&b), soroban_sdk::vec![&env, TaskLink { task_id: a, relation: LinkType::Blocks, outgoing: false }]);
+
+    // Volver a enlazar cambia la relación en ambos extremos
+    client.link_tasks(&a, &b, &owner_a, &LinkType::RelatesTo);
+    assert_eq!(client.get_links(&b).get_unchecked(0).relation, LinkType::RelatesTo);
+    assert_eq!(client.get_links(&a).len(), 1);
+
+    let result = client.try_link_tasks(&a, &b, &owner_a, &LinkType::RelatesTo);
+    assert_eq!(result.err().unwrap().unwrap(), TaskError::NoChange);
+}
@@ -0,0 +1,96 @@
+// --- UTILIDADES PARA TESTS (feature "testutils") ---
+
+// Helpers para que los tests de integración y los fuzzers construyan estados grandes
+// desde dentro del contrato, sin pasar por la API pública ni sus firmas.
+
+use soroban_sdk::{Address, Env, String, Vec};
+
+use crate::{
+    DataKey, Task, TaskStatus, ToDoListContract, NEXT_ID_KEY, STORAGE_VERSION, STORAGE_VERSION_KEY,
+};
+
+/// Crea 'n' tareas pendientes para 'owner' y retorna los IDs creados. Pasa por la misma
+/// inserción que 'add_task' (índices de propietario y de estado, numeración por propietario,
+/// contadores), pero sin validar la descripción ni exigir firmas.
+pub fn seed_tasks(env: &Env, contract_id: &Address, owner: &Address, n: u32) -> Vec<u64> {
+    env.as_contract(contract_id, || {
+        let mut ids: Vec<u64> = Vec::new(env);
+        for _ in 0..n {
+            let id = ToDoListContract::insert_task(env, String::from_str(env, "seed"), owner.clone(), TaskStatus::Pending)
+                .expect("contador de IDs agotado");
+            ids.push_back(id);
+        }
+        ids
+    })
+}
+
+/// Fuerza el estado de una tarea sin validaciones ni efectos secundarios (índices, timestamps)
+pub fn set_task_status_raw(env: &Env, contract_id: &Address, task_id: u64, status: TaskStatus) {
+    env.as_contract(contract_id, || {
+        let key = DataKey::Task(task_id);
+        let mut task: Task = env.storage().instance().get(&key).expect("tarea inexistente");
+        task.status = status;
+        env.storage().instance().set(&key, &task);
+    });
+}
+
+/// Comprueba los invariantes del almacenamiento y entra en pánico si alguno no se cumple.
+/// Pensado para harnesses de proptest/fuzzing tras cada operación generada: se pasa el
+/// 'next_id' devuelto por la llamada anterior (o 1) para verificar que nunca retrocede.
+/// Retorna el 'next_id' actual.
+pub fn check_invariants(env: &Env, contract_id: &Address, prev_next_id: u64) -> u64 {
+    env.as_contract(contract_id, || {
+        let storage = env.storage().instance();
+        let next_id: u64 = storage.get(&NEXT_ID_KEY).unwrap_or(1);
+
+        // 1. Monotonía del contador de IDs
+        assert!(next_id >= 1, "next_id debe empezar en 1");
+        assert!(next_id >= prev_next_id, "next_id retrocedió");
+
+        // 2. Contador correcto: todos los IDs emitidos tienen registro (o se archivaron en frío,
+        // o se purgaron sin dejar entradas auxiliares) y ninguno más allá
+        let mut owners: Vec<Address> = Vec::new(env);
+        for id in 1..next_id {
+            let task = match ToDoListContract::load_task(env, id) {
+                Some(task) => task,
+                None if storage.has(&DataKey::Cold(id)) => continue,
+                None => {
+                    assert!(
+                        !storage.has(&DataKey::TaskSeq(id))
+                            && !storage.has(&DataKey::DeletedAt(id))
+                            && !storage.has(&DataKey::AgedAt(id))
+                            && !storage.has(&DataKey::Hidden(id))
+                            && !storage.has(&DataKey::Recovery(id)),
+                        "tarea purgada con entradas auxiliares"
+                    );
+                    continue;
+                }
+            };
+            assert_eq!(task.id, id, "ID del registro no coincide con su clave");
+            assert!(task.updated_at >= task.timestamp, "updated_at anterior a la creación");
+            if !owners.contains(&task.owner) {
+                owners.push_back(task.owner.clone());
+            }
+        }
+        assert!(
+            ToDoListContract::load_task(env, next_id).is_none(),
+            "existe un registro con ID >= next_id"
+        );
+        if next_id > 1 && storage.has(&STORAGE_VERSION_KEY) {
+            assert_eq!(storage.get::<_, u32>(&STORAGE_VERSION_KEY), Some(STORAGE_VERSION));
+        }
+
+        // 3. Consistencia de índices: entradas válidas y sin duplicados
+        for owner in owners.iter() {
+            let index: Vec<u64> = storage.get(&owner).unwrap_or(Vec::new(env));
+            let mut seen: Vec<u64> = Vec::new(env);
+            for task_id in index.iter() {
+                assert!(task_id >= 1 && task_id < next_id, "índice apunta a un ID no emitido");
+                assert!(!seen.contains(task_id), "ID duplicado en el índice");
+                seen.push_back(task_id);
+            }
+        }
+
+        next_id
+    })
+}